@@ -9,7 +9,10 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn);
-        app.add_systems(Update, update.run_if(in_state(GameState::Playing)));
+        app.add_systems(
+            Update,
+            update.run_if(in_state(GameState::Playing).or(in_state(GameState::Editor))),
+        );
     }
 }
 
@@ -2,14 +2,15 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, EnumString};
 
 use crate::{
-    level::{LevelConfig, LevelHandle},
+    level::{LevelConfig, LevelHandle, LevelReloadedEvent},
     main_menu::MainMenuAssets,
     movement::{MovingProgress, Speed},
     pathfinding::{critter_cost_fn, heuristic, NeighborCostIter, PathState, SquareAreaCostIter},
-    tilemap::{AtlasHandle, Map, TilePos},
+    tilemap::{AtlasHandle, Map, TilePos, TilemapHandle},
     util::cleanup,
     GameState,
 };
@@ -21,6 +22,12 @@ impl Plugin for CritterPlugin {
         app.add_event::<SpawnCritterEvent>()
             .init_resource::<CritterRng>()
             .add_systems(OnEnter(GameState::Playing), setup)
+            .add_systems(
+                Update,
+                (cleanup::<CritterKind>, setup)
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and(on_event::<LevelReloadedEvent>)),
+            )
             .add_systems(OnEnter(GameState::MainMenu), setup_main_menu)
             .add_systems(
                 Update,
@@ -32,7 +39,9 @@ impl Plugin for CritterPlugin {
     }
 }
 
-#[derive(Component, Default, Deserialize, Debug, Copy, Clone)]
+#[derive(
+    Component, Default, Serialize, Deserialize, EnumIter, EnumString, Debug, Copy, Clone, PartialEq,
+)]
 #[require(Sprite, TilePos, MovingProgress, Speed, IdleTimer, CritterBehavior)]
 pub enum CritterKind {
     #[default]
@@ -83,13 +92,20 @@ fn setup(
     mut events: EventWriter<SpawnCritterEvent>,
     level_handle: Res<LevelHandle>,
     levels: Res<Assets<LevelConfig>>,
+    tilemap_handle: Res<TilemapHandle>,
+    maps: Res<Assets<Map>>,
 ) {
     let Some(level) = levels.get(&level_handle.0) else {
         warn!("Couldn't find level when spawning critters.");
         return;
     };
 
-    for (pos, kind) in &level.critters {
+    let map_critters = maps
+        .get(&tilemap_handle.0)
+        .map(|map| map.objects().critters.as_slice())
+        .unwrap_or_default();
+
+    for (pos, kind) in level.critters.iter().chain(map_critters) {
         events.write(SpawnCritterEvent {
             kind: *kind,
             pos: *pos,
@@ -101,13 +117,19 @@ fn setup_main_menu(
     mut events: EventWriter<SpawnCritterEvent>,
     main_menu_assets: Res<MainMenuAssets>,
     levels: Res<Assets<LevelConfig>>,
+    maps: Res<Assets<Map>>,
 ) {
     let Some(level) = levels.get(&main_menu_assets.level) else {
         warn!("Couldn't find level when spawning critters.");
         return;
     };
 
-    for (pos, kind) in &level.critters {
+    let map_critters = maps
+        .get(&main_menu_assets.map)
+        .map(|map| map.objects().critters.as_slice())
+        .unwrap_or_default();
+
+    for (pos, kind) in level.critters.iter().chain(map_critters) {
         events.write(SpawnCritterEvent {
             kind: *kind,
             pos: *pos,
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     level::{LevelConfig, LevelHandle},
@@ -16,7 +16,7 @@ impl Plugin for CurrencyPlugin {
 }
 
 pub struct NotEnoughCurrencyError;
-#[derive(Resource, Debug, Deserialize, Eq, PartialEq, Clone)]
+#[derive(Resource, Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct Currency {
     pub metal: u32,
     pub crystal: u32,
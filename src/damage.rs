@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{hit_points::HitPoints, particle::ParticleKind, GameState};
+
+pub struct DamagePlugin;
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DamagedEvent>()
+            .add_systems(Update, apply_damage.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    /// Enemy attacks and the stones fired by most towers.
+    #[default]
+    Physical,
+    Fire,
+    Crystal,
+}
+impl DamageType {
+    /// Tints the towers, their tool and the bullets they fire.
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Physical => Color::WHITE,
+            Self::Fire => Color::srgb(1.0, 0.55, 0.2),
+            Self::Crystal => Color::srgb(0.5, 0.85, 1.0),
+        }
+    }
+
+    /// Given off by whatever is hit by this type of damage.
+    pub fn particle(&self) -> ParticleKind {
+        match self {
+            Self::Physical => ParticleKind::Stone,
+            Self::Fire => ParticleKind::Fire,
+            Self::Crystal => ParticleKind::Crystal,
+        }
+    }
+}
+
+/// How much of each type of damage gets through. `0.5` halves the damage and
+/// `2.0` doubles it.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub crystal: f32,
+}
+impl Resistances {
+    pub const NONE: Self = Self {
+        physical: 1.,
+        fire: 1.,
+        crystal: 1.,
+    };
+
+    pub fn multiplier(&self, kind: DamageType) -> f32 {
+        match kind {
+            DamageType::Physical => self.physical,
+            DamageType::Fire => self.fire,
+            DamageType::Crystal => self.crystal,
+        }
+    }
+}
+impl Default for Resistances {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Reduces the damage taken by an entity. Entities without armor take the full
+/// amount.
+#[derive(Component, Debug, Default)]
+pub struct Armor {
+    /// Subtracted from every hit of physical damage, after resistances.
+    pub flat: f32,
+    pub resistances: Resistances,
+    /// Damage that didn't add up to a whole hit point, carried over to the next
+    /// hit so that resistances still matter against weak attacks.
+    leftover: f32,
+}
+impl Armor {
+    pub fn new(flat: f32, resistances: Resistances) -> Self {
+        Self {
+            flat,
+            resistances,
+            leftover: 0.,
+        }
+    }
+
+    /// The hit points taken by a hit of `amount` damage of the given type.
+    pub fn mitigate(&mut self, amount: u32, kind: DamageType) -> u32 {
+        let mut damage = amount as f32 * self.resistances.multiplier(kind).max(0.);
+        if kind == DamageType::Physical {
+            damage -= self.flat;
+        }
+
+        let total = damage.max(0.) + self.leftover;
+        let whole = total.floor();
+        self.leftover = total - whole;
+
+        whole as u32
+    }
+}
+
+/// Asks for `amount` damage to be dealt to the [`HitPoints`] of `target`, after
+/// its [`Armor`].
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: u32,
+    pub kind: DamageType,
+}
+
+/// Sent after a [`DamageEvent`] has been applied to a target that was still
+/// alive.
+#[derive(Event, Debug)]
+pub struct DamagedEvent {
+    pub target: Entity,
+    /// The hit points actually taken.
+    pub amount: u32,
+    pub kind: DamageType,
+    /// Whether the hit brought the target to zero hit points.
+    pub killed: bool,
+}
+
+pub fn apply_damage(
+    mut events: EventReader<DamageEvent>,
+    mut query: Query<(&mut HitPoints, Option<&mut Armor>)>,
+    mut damaged_writer: EventWriter<DamagedEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut hp, armor)) = query.get_mut(event.target) else {
+            continue;
+        };
+
+        // Several hits may land on the same frame that the target dies.
+        if hp.is_zero() {
+            continue;
+        }
+
+        let amount = match armor {
+            Some(mut armor) => armor.mitigate(event.amount, event.kind),
+            None => event.amount,
+        };
+
+        hp.sub(amount);
+
+        damaged_writer.write(DamagedEvent {
+            target: event.target,
+            amount,
+            kind: event.kind,
+            killed: hp.is_zero(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unarmored_takes_full_damage() {
+        let mut armor = Armor::default();
+
+        assert_eq!(armor.mitigate(3, DamageType::Physical), 3);
+        assert_eq!(armor.mitigate(3, DamageType::Fire), 3);
+        assert_eq!(armor.mitigate(3, DamageType::Crystal), 3);
+    }
+
+    #[test]
+    fn resistances_multiply_damage() {
+        let mut armor = Armor::new(
+            0.,
+            Resistances {
+                fire: 2.,
+                crystal: 0.,
+                ..Resistances::NONE
+            },
+        );
+
+        assert_eq!(armor.mitigate(3, DamageType::Fire), 6);
+        assert_eq!(armor.mitigate(3, DamageType::Crystal), 0);
+        assert_eq!(armor.mitigate(3, DamageType::Physical), 3);
+    }
+
+    #[test]
+    fn flat_armor_only_stops_physical_damage() {
+        let mut armor = Armor::new(2., Resistances::NONE);
+
+        assert_eq!(armor.mitigate(3, DamageType::Physical), 1);
+        assert_eq!(armor.mitigate(1, DamageType::Physical), 0);
+        assert_eq!(armor.mitigate(3, DamageType::Fire), 3);
+    }
+
+    #[test]
+    fn leftover_carries_to_the_next_hit() {
+        let mut armor = Armor::new(
+            0.,
+            Resistances {
+                physical: 0.75,
+                ..Resistances::NONE
+            },
+        );
+
+        let hits = (0..4)
+            .map(|_| armor.mitigate(1, DamageType::Physical))
+            .collect::<Vec<_>>();
+
+        assert_eq!(hits, vec![0, 1, 1, 1]);
+        assert_eq!(armor.leftover, 0.);
+    }
+}
@@ -7,6 +7,7 @@ use bevy::{
 use crate::{
     currency::Currency,
     cursor::CursorSnapped,
+    damage::DamageType,
     layer,
     settings::SfxSetting,
     sound::SoundAssets,
@@ -18,6 +19,7 @@ use crate::{
 
 const DESIGNATE_DIG_OK: Color = Color::srgba(0., 1.0, 1.0, 0.5);
 const DESIGNATE_DANCE_OK: Color = Color::srgba(1.0, 0.0, 1.0, 0.2);
+const DESIGNATE_REPAIR_OK: Color = Color::srgba(0.2, 1.0, 0.2, 0.6);
 const DESIGNATE_NOT_OK: Color = Color::srgba(1.0, 0.0, 0.0, 0.8);
 
 pub struct DesignateToolPlugin;
@@ -49,13 +51,15 @@ struct DesignateToolRange;
 #[derive(Copy, Clone, Debug)]
 pub enum DesignationKind {
     Dig,
-    BuildTower,
+    BuildTower(DamageType),
     Dance,
+    /// Made automatically when a tower is damaged.
+    Repair,
 }
 impl From<Tool> for DesignationKind {
     fn from(value: Tool) -> Self {
         match value {
-            Tool::BuildTower => DesignationKind::BuildTower,
+            Tool::BuildTower(damage_type) => DesignationKind::BuildTower(damage_type),
             Tool::Dig => DesignationKind::Dig,
             Tool::Dance => DesignationKind::Dance,
         }
@@ -66,7 +70,9 @@ impl DesignationKind {
         match ok {
             true => match self {
                 DesignationKind::Dig => TileKind::WhitePickaxe.atlas_index(),
-                DesignationKind::BuildTower => TileKind::TowerBlueprint.atlas_index(),
+                DesignationKind::BuildTower(_) | DesignationKind::Repair => {
+                    TileKind::TowerBlueprint.atlas_index()
+                }
                 DesignationKind::Dance => TileKind::White.atlas_index(),
             },
             false => TileKind::WhiteCircleNo.atlas_index(),
@@ -76,19 +82,31 @@ impl DesignationKind {
         match ok {
             true => match self {
                 DesignationKind::Dig => DESIGNATE_DIG_OK,
-                DesignationKind::BuildTower => Color::srgb_u8(82, 94, 173),
+                DesignationKind::BuildTower(DamageType::Physical) => Color::srgb_u8(82, 94, 173),
+                DesignationKind::BuildTower(damage_type) => damage_type.color(),
                 DesignationKind::Dance => DESIGNATE_DANCE_OK,
+                DesignationKind::Repair => DESIGNATE_REPAIR_OK,
             },
             false => DESIGNATE_NOT_OK,
         }
     }
     pub fn price(&self) -> Currency {
         match self {
-            DesignationKind::BuildTower => Currency {
+            DesignationKind::BuildTower(DamageType::Physical) => Currency {
                 metal: 1,
                 stone: 15,
                 crystal: 0,
             },
+            DesignationKind::BuildTower(DamageType::Fire) => Currency {
+                metal: 3,
+                stone: 15,
+                crystal: 0,
+            },
+            DesignationKind::BuildTower(DamageType::Crystal) => Currency {
+                metal: 1,
+                stone: 15,
+                crystal: 3,
+            },
             _ => Currency::ZERO,
         }
     }
@@ -133,7 +151,7 @@ fn init_cursor(mut commands: Commands, atlas_handle: Res<AtlasHandle>, ui_assets
         .with_children(|parent| {
             parent.spawn((
                 Sprite {
-                    color: DesignationKind::BuildTower.color(true),
+                    color: DesignationKind::BuildTower(DamageType::Physical).color(true),
                     image: ui_assets.range_indicator_24.clone(),
                     ..default()
                 },
@@ -178,8 +196,8 @@ fn update_cursor(
         let designation = DesignationKind::from(selected_tool.0);
 
         let ok = match selected_tool.0 {
-            Tool::Dig if kind.diggable() => true,
-            Tool::BuildTower | Tool::Dance if kind.buildable() => true,
+            Tool::Dig if tilemap.properties().diggable(*kind) => true,
+            Tool::BuildTower(_) | Tool::Dance if tilemap.properties().buildable(*kind) => true,
             _ => false,
         };
 
@@ -193,7 +211,7 @@ fn update_cursor(
 
         for mut visibility in &mut range_query {
             match selected_tool.0 {
-                Tool::BuildTower if ok && has_money => {
+                Tool::BuildTower(_) if ok && has_money => {
                     *visibility = Visibility::Inherited;
                 }
                 _ => {
@@ -217,7 +235,7 @@ fn show_cursor(
     };
 
     *visibility = match selected_tool.0 {
-        Tool::Dig | Tool::BuildTower | Tool::Dance => Visibility::Visible,
+        Tool::Dig | Tool::BuildTower(_) | Tool::Dance => Visibility::Visible,
     };
 }
 
@@ -323,8 +341,8 @@ fn designate(
     };
 
     let ok = match selected_tool.0 {
-        Tool::Dig if kind.diggable() => true,
-        Tool::BuildTower | Tool::Dance if kind.buildable() => true,
+        Tool::Dig if tilemap.properties().diggable(*kind) => true,
+        Tool::BuildTower(_) | Tool::Dance if tilemap.properties().buildable(*kind) => true,
         _ => false,
     };
 
@@ -349,34 +367,49 @@ fn designate(
         return;
     }
 
-    let id = commands
+    let id = spawn_marker(
+        &mut commands,
+        &atlas_handle,
+        designation_kind,
+        world_pos_snapped,
+    );
+
+    designations.0.insert(
+        tile_pos,
+        Designation {
+            kind: designation_kind,
+            indicator: id,
+            workers: 0,
+        },
+    );
+
+    tool_state.touched.insert(tile_pos);
+}
+
+/// Spawns the indicator that shows where a designation is.
+pub fn spawn_marker(
+    commands: &mut Commands,
+    atlas_handle: &AtlasHandle,
+    kind: DesignationKind,
+    world_pos: Vec2,
+) -> Entity {
+    commands
         .spawn((
             Sprite {
                 image: atlas_handle.image.clone(),
-                color: designation_kind.color(true),
+                color: kind.color(true),
                 texture_atlas: Some(TextureAtlas {
                     layout: atlas_handle.layout.clone(),
-                    index: designation_kind.atlas_index(true),
+                    index: kind.atlas_index(true),
                 }),
                 ..default()
             },
-            Transform::from_translation(world_pos_snapped.extend(layer::BLUEPRINT))
+            Transform::from_translation(world_pos.extend(layer::BLUEPRINT))
                 .with_scale(crate::tilemap::SCALE.extend(1.)),
             DesignationMarker,
             Name::new("DesignationMarker"),
         ))
-        .id();
-
-    designations.0.insert(
-        tile_pos,
-        Designation {
-            kind: designation_kind,
-            indicator: id,
-            workers: 0,
-        },
-    );
-
-    tool_state.touched.insert(tile_pos);
+        .id()
 }
 
 fn cleanup(
@@ -0,0 +1,1156 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, ui::FocusPolicy};
+use grid::Grid;
+use ron::ser::PrettyConfig;
+use strum::IntoEnumIterator;
+
+use crate::{
+    critter::CritterKind,
+    cursor::{Cursor, CursorSnapped},
+    enemy::EnemyKind,
+    enemy_table::{Enemies, EnemyTable},
+    layer,
+    level::{LevelConfig, LevelHandle, LevelHandles},
+    map_loader::MapFileLoaderSettings,
+    palette::{Palette, DEFAULT_PALETTE},
+    radio_button::{RadioButton, RadioButtonGroup, RadioButtonGroupRelation},
+    spawner::{Spawn, SpawnTrigger},
+    tilemap::{
+        AtlasHandle, Map, TileEntities, TileKind, TilemapBundle, TilemapHandle, SCALE, TILE_SIZE,
+    },
+    tools::{read_map, read_palette, write_map, ToolError, ASSETS_DIR},
+    ui::{slice_image_mode, UiAssets, BUTTON_TEXT, TITLE_TEXT},
+    validation::validate_level,
+    waves::Wave,
+    worker::WORKER_SPRITES,
+    GameState,
+};
+
+const HIDDEN_CRYSTAL: Color = Color::srgb(0.6, 0.8, 1.0);
+const HIDDEN_METAL: Color = Color::srgb(1.0, 0.75, 0.5);
+const CURSOR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
+
+pub struct EditorPlugin;
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brush>()
+            .add_systems(OnEnter(GameState::Editor), (init, init_ui).chain())
+            .add_systems(
+                Update,
+                (
+                    (paint, update_markers, update_spawner_labels).chain(),
+                    (
+                        select_brush,
+                        update_brush_style,
+                        update_brush_text,
+                        update_cursor,
+                    ),
+                    tint_hidden_tiles,
+                )
+                    .run_if(in_state(GameState::Editor).and(resource_exists::<EditorLevel>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    (wave_buttons, spawn_buttons, update_wave_panel).chain(),
+                    (save, update_status_text).chain(),
+                    back,
+                )
+                    .run_if(in_state(GameState::Editor).and(resource_exists::<EditorLevel>)),
+            )
+            .add_systems(OnExit(GameState::Editor), cleanup);
+    }
+}
+
+/// The level being edited. Its map is edited on the tilemap entity instead.
+#[derive(Resource)]
+pub struct EditorLevel {
+    /// The path of the level file, relative to the assets directory.
+    pub path: PathBuf,
+    pub config: LevelConfig,
+    /// The palette that the map is saved with.
+    pub palette: Palette,
+}
+
+/// The index of the wave shown in the wave panel.
+#[derive(Resource, Default)]
+struct EditorWave(usize);
+
+#[derive(Resource, Default)]
+struct EditorStatus(String);
+
+/// What gets placed when clicking on the map.
+#[derive(Resource, Component, Clone, Copy, Debug, PartialEq)]
+pub enum Brush {
+    Tile(TileKind),
+    Critter(CritterKind),
+    WorkerSpawn,
+}
+impl Default for Brush {
+    fn default() -> Self {
+        Self::Tile(TileKind::Dirt)
+    }
+}
+impl Brush {
+    fn atlas_index(&self) -> usize {
+        match self {
+            Self::Tile(kind) => kind.atlas_index(),
+            Self::Critter(kind) => kind.atlas_index(),
+            Self::WorkerSpawn => WORKER_SPRITES[0],
+        }
+    }
+    fn name(&self) -> String {
+        match self {
+            Self::Tile(kind) => format!("{kind:?}"),
+            Self::Critter(kind) => format!("{kind:?}"),
+            Self::WorkerSpawn => "Worker spawn".to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct BrushButton;
+#[derive(Component)]
+struct BrushText;
+#[derive(Component)]
+struct EditorCursor;
+#[derive(Component)]
+struct WavePanel;
+#[derive(Component)]
+struct StatusText;
+#[derive(Component)]
+struct SaveButton;
+#[derive(Component)]
+struct BackButton;
+
+/// A critter or the worker spawn shown on the map.
+#[derive(Component)]
+struct Marker;
+#[derive(Component)]
+struct SpawnerLabel;
+
+#[derive(Component, Clone, Copy)]
+enum WaveButton {
+    Previous,
+    Next,
+    Add,
+    Remove,
+    AddSpawn,
+    RemoveSpawn,
+}
+
+/// A button that changes one field of a [`Spawn`] in the current wave.
+#[derive(Component, Clone, Copy)]
+struct SpawnButton {
+    spawn: usize,
+    field: SpawnField,
+}
+
+#[derive(Clone, Copy)]
+enum SpawnField {
+    Spawner,
+    Kind,
+    Num,
+    Hp,
+    Delay,
+    Interval,
+}
+
+fn init(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<LevelConfig>>,
+    tilemap_handle: Res<TilemapHandle>,
+    maps: Res<Assets<Map>>,
+    atlas_handle: Res<AtlasHandle>,
+    brush: Res<Brush>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let (Some(path), Some(level), Some(map)) = (
+        asset_server.get_path(level_handle.0.id()),
+        levels.get(&level_handle.0),
+        maps.get(&tilemap_handle.0),
+    ) else {
+        warn!("Couldn't find the level to edit.");
+        next_state.set(GameState::MainMenu);
+        return;
+    };
+
+    let palette_path =
+        Path::new(ASSETS_DIR).join(level.palette.as_deref().unwrap_or(DEFAULT_PALETTE));
+    let palette = match read_palette(&palette_path) {
+        Ok(palette) => palette,
+        Err(e) => {
+            error!("Couldn't read the level's palette: {e}");
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+    };
+
+    let mut config = level.clone();
+    let mut map = map.clone();
+
+    // Critters placed by the map file are edited along with the level's own,
+    // because the map formats that the editor saves can't store them.
+    config.critters.append(&mut map.2.critters);
+
+    commands.insert_resource(EditorLevel {
+        path: path.path().to_path_buf(),
+        config,
+        palette,
+    });
+    commands.insert_resource(EditorWave::default());
+    commands.insert_resource(EditorStatus::default());
+
+    let entities = TileEntities(Grid::new(map.0.rows(), map.0.cols()));
+
+    commands.spawn((
+        TilemapBundle {
+            tilemap_handle: tilemap_handle.clone(),
+            atlas_handle: atlas_handle.clone(),
+            tiles: map,
+            entities,
+        },
+        StateScoped(GameState::Editor),
+    ));
+
+    commands.spawn((
+        Sprite {
+            color: CURSOR_COLOR,
+            image: atlas_handle.image.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: atlas_handle.layout.clone(),
+                index: brush.atlas_index(),
+            }),
+            ..default()
+        },
+        Transform::from_xyz(0., 0., layer::CURSOR).with_scale(SCALE.extend(1.)),
+        EditorCursor,
+        StateScoped(GameState::Editor),
+    ));
+
+    for mut transform in &mut camera_query {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
+
+fn init_ui(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    atlas_handle: Res<AtlasHandle>,
+    level: Option<Res<EditorLevel>>,
+    mut brush: ResMut<Brush>,
+) {
+    // The level couldn't be loaded, and we're heading back to the main menu.
+    let Some(level) = level else {
+        return;
+    };
+
+    let button_node = (
+        Node {
+            height: Val::Px(30.0),
+            padding: UiRect::horizontal(Val::Px(10.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ImageNode {
+            image: ui_assets.nine_button.clone(),
+            image_mode: slice_image_mode(),
+            ..default()
+        },
+    );
+    let button_text_style = (
+        TextFont {
+            font_size: 15.0,
+            ..default()
+        },
+        TextColor(BUTTON_TEXT),
+    );
+    let title_text_style = (
+        TextFont {
+            font_size: 15.0,
+            ..default()
+        },
+        TextColor(TITLE_TEXT),
+    );
+
+    // Only tiles that the palette has a color for can be saved to a map image.
+    let brushes = TileKind::iter()
+        .filter(|kind| level.palette.colors.values().any(|k| k == kind))
+        .map(Brush::Tile)
+        .chain(CritterKind::iter().map(Brush::Critter))
+        .chain([Brush::WorkerSpawn])
+        .collect::<Vec<_>>();
+
+    if !brushes.contains(&brush) {
+        *brush = brushes[0];
+    }
+
+    let mut brush_button_ids = vec![];
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(5.),
+                top: Val::Px(5.),
+                bottom: Val::Px(5.),
+                width: Val::Px(320.),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.)),
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            ImageNode {
+                image: ui_assets.nine_panel.clone(),
+                image_mode: slice_image_mode(),
+                ..default()
+            },
+            // Keep clicks on the panel from painting the map behind it.
+            Interaction::default(),
+            FocusPolicy::Block,
+            StateScoped(GameState::Editor),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("Editing {}", level.path.display())),
+                title_text_style.clone(),
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    row_gap: Val::Px(4.),
+                    column_gap: Val::Px(4.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for b in &brushes {
+                        let id = parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(40.0),
+                                    height: Val::Px(40.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: ui_assets.nine_button.clone(),
+                                    image_mode: slice_image_mode(),
+                                    ..default()
+                                },
+                                RadioButton {
+                                    selected: *b == *brush,
+                                },
+                                BrushButton,
+                                *b,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Node {
+                                        width: Val::Px(TILE_SIZE.x * SCALE.x),
+                                        height: Val::Px(TILE_SIZE.y * SCALE.y),
+                                        ..default()
+                                    },
+                                    ImageNode {
+                                        image: atlas_handle.image.clone(),
+                                        texture_atlas: Some(TextureAtlas {
+                                            layout: atlas_handle.layout.clone(),
+                                            index: b.atlas_index(),
+                                        }),
+                                        color: match b {
+                                            Brush::Tile(kind) => hidden_tile_color(*kind),
+                                            _ => Color::WHITE,
+                                        },
+                                        ..default()
+                                    },
+                                ));
+                            })
+                            .id();
+
+                        brush_button_ids.push(id);
+                    }
+                });
+
+            parent.spawn((Text::new(brush.name()), title_text_style.clone(), BrushText));
+
+            parent.spawn((
+                Text::new("Left click to paint, right click to erase"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(TITLE_TEXT),
+            ));
+
+            parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.),
+                    flex_grow: 1.,
+                    ..default()
+                },
+                WavePanel,
+            ));
+
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(TITLE_TEXT),
+                StatusText,
+            ));
+
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(6.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((Button, button_node.clone(), SaveButton))
+                        .with_children(|parent| {
+                            parent.spawn((Text::new("Save"), button_text_style.clone()));
+                        });
+                    parent
+                        .spawn((Button, button_node.clone(), BackButton))
+                        .with_children(|parent| {
+                            parent.spawn((Text::new("Back"), button_text_style.clone()));
+                        });
+                });
+        });
+
+    let group = commands
+        .spawn((
+            RadioButtonGroup {
+                entities: brush_button_ids.clone(),
+            },
+            StateScoped(GameState::Editor),
+        ))
+        .id();
+
+    for id in brush_button_ids {
+        commands.entity(id).insert(RadioButtonGroupRelation(group));
+    }
+}
+
+fn hidden_tile_color(kind: TileKind) -> Color {
+    match kind {
+        TileKind::CrystalHidden => HIDDEN_CRYSTAL,
+        TileKind::MetalHidden => HIDDEN_METAL,
+        _ => Color::WHITE,
+    }
+}
+
+/// Hidden crystal and metal look just like stone, so they are tinted while editing.
+fn tint_hidden_tiles(mut query: Query<(&TileKind, &mut Sprite), Changed<TileKind>>) {
+    for (kind, mut sprite) in &mut query {
+        sprite.color = hidden_tile_color(*kind);
+    }
+}
+
+fn select_brush(
+    query: Query<(&RadioButton, &Brush), (Changed<RadioButton>, With<BrushButton>)>,
+    mut brush: ResMut<Brush>,
+) {
+    for (radio, b) in &query {
+        if radio.selected {
+            *brush = *b;
+        }
+    }
+}
+
+fn update_brush_style(
+    mut query: Query<(&RadioButton, &mut ImageNode), (Changed<RadioButton>, With<BrushButton>)>,
+    ui_assets: Res<UiAssets>,
+) {
+    for (radio, mut image_node) in &mut query {
+        image_node.image = if radio.selected {
+            ui_assets.nine_button_selected.clone()
+        } else {
+            ui_assets.nine_button.clone()
+        };
+    }
+}
+
+fn update_brush_text(brush: Res<Brush>, mut query: Query<&mut Text, With<BrushText>>) {
+    if !brush.is_changed() {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.0 = brush.name();
+    }
+}
+
+fn update_cursor(
+    brush: Res<Brush>,
+    cursor_snapped: Res<CursorSnapped>,
+    mut query: Query<(&mut Transform, &mut Sprite), With<EditorCursor>>,
+) {
+    if !brush.is_changed() && !cursor_snapped.is_changed() {
+        return;
+    }
+
+    for (mut transform, mut sprite) in &mut query {
+        if let Some(snapped) = cursor_snapped.world_pos {
+            transform.translation.x = snapped.x;
+            transform.translation.y = snapped.y;
+        }
+
+        if let Some(ref mut atlas) = sprite.texture_atlas {
+            atlas.index = brush.atlas_index();
+        }
+    }
+}
+
+fn paint(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: Res<Cursor>,
+    cursor_snapped: Res<CursorSnapped>,
+    brush: Res<Brush>,
+    interaction_query: Query<&Interaction>,
+    mut tilemap_query: Query<(&mut Map, &TileEntities)>,
+    mut tile_query: Query<(&mut TileKind, &mut Sprite)>,
+    mut level: ResMut<EditorLevel>,
+) {
+    let erase = buttons.pressed(MouseButton::Right);
+    if !erase && !buttons.pressed(MouseButton::Left) {
+        return;
+    }
+
+    if !buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
+        && !cursor_snapped.is_changed()
+    {
+        return;
+    }
+
+    if interaction_query.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    let Some(pos) = cursor_snapped.tile_pos else {
+        return;
+    };
+
+    let Ok((mut map, entities)) = tilemap_query.single_mut() else {
+        return;
+    };
+
+    // Tile positions are clamped to the edge of the map, so make sure that the
+    // cursor is actually over it.
+    let half_size = map.size_vec2() * SCALE * TILE_SIZE / 2.;
+    if cursor.world_pos.abs().cmpgt(half_size).any() {
+        return;
+    }
+
+    let Some(current) = map.0.get(pos.y, pos.x).copied() else {
+        return;
+    };
+
+    let has_critter = level.config.critters.iter().any(|(p, _)| *p == pos);
+
+    let tile = match *brush {
+        _ if erase && has_critter => {
+            level.config.critters.retain(|(p, _)| *p != pos);
+            return;
+        }
+        _ if erase && level.config.worker_spawn == Some(pos) => {
+            level.config.worker_spawn = None;
+            return;
+        }
+        _ if erase => TileKind::Dirt,
+        Brush::Tile(kind) => kind,
+        Brush::Critter(kind) => {
+            if !level.config.critters.contains(&(pos, kind)) {
+                level.config.critters.retain(|(p, _)| *p != pos);
+                level.config.critters.push((pos, kind));
+            }
+            return;
+        }
+        Brush::WorkerSpawn => {
+            if level.config.worker_spawn != Some(pos) {
+                level.config.worker_spawn = Some(pos);
+            }
+            return;
+        }
+    };
+
+    if current == tile {
+        return;
+    }
+
+    map.0[(pos.y, pos.x)] = tile;
+    // Spawners placed in the editor are numbered by their position.
+    map.2.spawners.remove(&pos);
+
+    let Some(Some(entity)) = entities.0.get(pos.y, pos.x) else {
+        return;
+    };
+
+    let Ok((mut kind, mut sprite)) = tile_query.get_mut(*entity) else {
+        return;
+    };
+
+    *kind = tile;
+    if let Some(ref mut atlas) = sprite.texture_atlas {
+        atlas.index = tile.atlas_index();
+    }
+}
+
+fn update_markers(
+    mut commands: Commands,
+    level: Res<EditorLevel>,
+    tilemap_query: Query<&Map, With<TileEntities>>,
+    marker_query: Query<Entity, With<Marker>>,
+    atlas_handle: Res<AtlasHandle>,
+) {
+    if !level.is_changed() {
+        return;
+    }
+
+    let Ok(map) = tilemap_query.single() else {
+        return;
+    };
+
+    for entity in &marker_query {
+        commands.entity(entity).despawn();
+    }
+
+    let markers = level
+        .config
+        .critters
+        .iter()
+        .map(|(pos, kind)| (*pos, kind.atlas_index()))
+        .chain(
+            level
+                .config
+                .worker_spawn
+                .map(|pos| (pos, WORKER_SPRITES[0])),
+        );
+
+    for (pos, index) in markers {
+        commands.spawn((
+            Sprite {
+                image: atlas_handle.image.clone(),
+                texture_atlas: Some(TextureAtlas {
+                    layout: atlas_handle.layout.clone(),
+                    index,
+                }),
+                ..default()
+            },
+            Transform::from_translation(map.pos_to_world(pos).extend(layer::MOBS))
+                .with_scale(SCALE.extend(1.)),
+            Marker,
+            StateScoped(GameState::Editor),
+        ));
+    }
+}
+
+fn update_spawner_labels(
+    mut commands: Commands,
+    tilemap_query: Query<Ref<Map>, With<TileEntities>>,
+    label_query: Query<Entity, With<SpawnerLabel>>,
+) {
+    let Ok(map) = tilemap_query.single() else {
+        return;
+    };
+
+    if !map.is_changed() {
+        return;
+    }
+
+    for entity in &label_query {
+        commands.entity(entity).despawn();
+    }
+
+    for (pos, index) in map.spawners() {
+        commands.spawn((
+            Text2d::new(index.to_string()),
+            TextFont {
+                font_size: 15.0,
+                ..default()
+            },
+            TextColor(TITLE_TEXT),
+            Transform::from_translation(map.pos_to_world(pos).extend(layer::CURSOR - 1.)),
+            SpawnerLabel,
+            StateScoped(GameState::Editor),
+        ));
+    }
+}
+
+fn wave_buttons(
+    query: Query<(&Interaction, &WaveButton), Changed<Interaction>>,
+    mut level: ResMut<EditorLevel>,
+    mut wave: ResMut<EditorWave>,
+) {
+    for (interaction, button) in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let waves = &mut level.config.waves;
+
+        match button {
+            WaveButton::Previous => wave.0 = wave.0.saturating_sub(1),
+            WaveButton::Next => wave.0 = (wave.0 + 1).min(waves.len().saturating_sub(1)),
+            WaveButton::Add => {
+                // New waves start out as a copy of the current one.
+                let new = waves.get(wave.0).cloned().unwrap_or_else(|| Wave {
+                    spawns: vec![default_spawn()],
+                    timeout: None,
+                });
+                let index = (wave.0 + 1).min(waves.len());
+                waves.insert(index, new);
+                wave.0 = index;
+            }
+            WaveButton::Remove => {
+                if wave.0 < waves.len() {
+                    waves.remove(wave.0);
+                }
+                wave.0 = wave.0.min(waves.len().saturating_sub(1));
+            }
+            WaveButton::AddSpawn => {
+                if let Some(current) = waves.get_mut(wave.0) {
+                    let spawn = current.spawns.last().cloned().unwrap_or_else(default_spawn);
+                    current.spawns.push(spawn);
+                }
+            }
+            WaveButton::RemoveSpawn => {
+                if let Some(current) = waves.get_mut(wave.0) {
+                    current.spawns.pop();
+                }
+            }
+        }
+    }
+}
+
+fn default_spawn() -> Spawn {
+    Spawn {
+        spawner: 0,
+        num: 10,
+        delay: 15.,
+        interval: 2.,
+        hp: 4,
+        kind: EnemyKind::Skeleton,
+        speed: None,
+        damage: None,
+        reward: None,
+        group: None,
+        trigger: SpawnTrigger::WaveStart,
+    }
+}
+
+fn spawn_buttons(
+    query: Query<(&Interaction, &SpawnButton), Changed<Interaction>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    tilemap_query: Query<&Map, With<TileEntities>>,
+    mut level: ResMut<EditorLevel>,
+    wave: Res<EditorWave>,
+) {
+    let decrease = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for (interaction, button) in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some(spawn) = level
+            .config
+            .waves
+            .get_mut(wave.0)
+            .and_then(|wave| wave.spawns.get_mut(button.spawn))
+        else {
+            continue;
+        };
+
+        match button.field {
+            SpawnField::Spawner => {
+                let mut indices = tilemap_query
+                    .single()
+                    .map(|map| map.spawners())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(_, index)| index)
+                    .collect::<Vec<_>>();
+                indices.sort();
+                spawn.spawner = cycle(&indices, spawn.spawner, decrease);
+            }
+            SpawnField::Kind => {
+                let kinds = EnemyKind::iter().collect::<Vec<_>>();
+                spawn.kind = cycle(&kinds, spawn.kind, decrease);
+            }
+            SpawnField::Num if decrease => spawn.num = spawn.num.saturating_sub(1).max(1),
+            SpawnField::Num => spawn.num += 1,
+            SpawnField::Hp if decrease => spawn.hp = spawn.hp.saturating_sub(1).max(1),
+            SpawnField::Hp => spawn.hp += 1,
+            SpawnField::Delay if decrease => spawn.delay = (spawn.delay - 1.).max(0.),
+            SpawnField::Delay => spawn.delay += 1.,
+            SpawnField::Interval if decrease => spawn.interval = (spawn.interval - 0.5).max(0.),
+            SpawnField::Interval => spawn.interval += 0.5,
+        }
+    }
+}
+
+/// Returns the value after `current` in `values`, or the one before it when going
+/// backwards, wrapping around at the ends.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, backwards: bool) -> T {
+    let Some(i) = values.iter().position(|v| *v == current) else {
+        return values.first().copied().unwrap_or(current);
+    };
+
+    let i = if backwards {
+        (i + values.len() - 1) % values.len()
+    } else {
+        (i + 1) % values.len()
+    };
+
+    values[i]
+}
+
+fn update_wave_panel(
+    mut commands: Commands,
+    level: Res<EditorLevel>,
+    wave: Res<EditorWave>,
+    panel_query: Query<Entity, With<WavePanel>>,
+    ui_assets: Res<UiAssets>,
+) {
+    if !level.is_changed() && !wave.is_changed() {
+        return;
+    }
+
+    let Ok(panel) = panel_query.single() else {
+        return;
+    };
+
+    commands.entity(panel).despawn_related::<Children>();
+
+    let button = |text: String| {
+        (
+            Button,
+            Node {
+                height: Val::Px(26.0),
+                padding: UiRect::horizontal(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ImageNode {
+                image: ui_assets.nine_button.clone(),
+                image_mode: slice_image_mode(),
+                ..default()
+            },
+            children![(
+                Text::new(text),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(BUTTON_TEXT),
+            )],
+        )
+    };
+    let row = || Node {
+        flex_wrap: FlexWrap::Wrap,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(4.),
+        column_gap: Val::Px(4.),
+        ..default()
+    };
+    let text_style = (
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(TITLE_TEXT),
+    );
+
+    let waves = &level.config.waves;
+    let current = waves.get(wave.0);
+
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(row()).with_children(|parent| {
+            parent.spawn((
+                Text::new(match current {
+                    Some(_) => format!("Wave {}/{}", wave.0 + 1, waves.len()),
+                    None => "No waves".to_string(),
+                }),
+                text_style.clone(),
+            ));
+            parent.spawn((button("<".into()), WaveButton::Previous));
+            parent.spawn((button(">".into()), WaveButton::Next));
+            parent.spawn((button("Add".into()), WaveButton::Add));
+            parent.spawn((button("Remove".into()), WaveButton::Remove));
+        });
+
+        let Some(current) = current else {
+            return;
+        };
+
+        for (i, spawn) in current.spawns.iter().enumerate() {
+            parent.spawn(row()).with_children(|parent| {
+                let fields = [
+                    (SpawnField::Spawner, format!("Spawner {}", spawn.spawner)),
+                    (SpawnField::Kind, format!("{:?}", spawn.kind)),
+                    (SpawnField::Num, format!("x{}", spawn.num)),
+                    (SpawnField::Hp, format!("{} hp", spawn.hp)),
+                    (SpawnField::Delay, format!("after {}s", spawn.delay)),
+                    (SpawnField::Interval, format!("every {}s", spawn.interval)),
+                ];
+
+                for (field, text) in fields {
+                    parent.spawn((button(text), SpawnButton { spawn: i, field }));
+                }
+            });
+        }
+
+        parent.spawn(row()).with_children(|parent| {
+            parent.spawn((button("Add spawn".into()), WaveButton::AddSpawn));
+            parent.spawn((button("Remove spawn".into()), WaveButton::RemoveSpawn));
+        });
+
+        parent.spawn((
+            Text::new("Click to increase, Shift+click to decrease"),
+            text_style.clone(),
+        ));
+    });
+}
+
+fn save(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SaveButton>)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut level: ResMut<EditorLevel>,
+    mut tilemap_query: Query<(&mut Map, &mut TilemapHandle), With<TileEntities>>,
+    asset_server: Res<AssetServer>,
+    level_handle: Res<LevelHandle>,
+    mut level_handles: ResMut<LevelHandles>,
+    mut levels: ResMut<Assets<LevelConfig>>,
+    mut maps: ResMut<Assets<Map>>,
+    mut status: ResMut<EditorStatus>,
+    enemies: Enemies,
+) {
+    let pressed = interaction_query.iter().any(|i| *i == Interaction::Pressed)
+        || (keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && keys.just_pressed(KeyCode::KeyS));
+    if !pressed {
+        return;
+    }
+
+    let Ok((mut map, mut tilemap_handle)) = tilemap_query.single_mut() else {
+        return;
+    };
+
+    let old_map = level.config.map.clone();
+
+    if let Err(e) = save_level(&mut level, &mut map) {
+        error!("Couldn't save level: {e}");
+        status.0 = format!("Couldn't save: {e}");
+        return;
+    }
+
+    // The map was saved in a new format, so switch every handle to the new file.
+    if level.config.map != old_map {
+        let strict = level.config.strict_map;
+        let palette = level.config.palette.clone();
+        let handle = asset_server.load_with_settings::<Map, MapFileLoaderSettings>(
+            level.config.map.clone(),
+            move |settings: &mut MapFileLoaderSettings| {
+                settings.strict = strict;
+                settings.palette.clone_from(&palette);
+            },
+        );
+
+        for map_handle in &mut level_handles.maps {
+            if *map_handle == tilemap_handle.0 {
+                *map_handle = handle.clone();
+            }
+        }
+
+        commands.insert_resource(TilemapHandle(handle.clone()));
+        *tilemap_handle = TilemapHandle(handle);
+    }
+
+    // Play the edited level without having to restart the game, or wait for the
+    // map to load.
+    maps.insert(tilemap_handle.0.id(), map.clone());
+    if let Some(asset) = levels.get_mut(&level_handle.0) {
+        *asset = level.config.clone();
+    }
+
+    // Check the map as it was written, which is what the level will load next
+    // time.
+    let saved_map = match read_map(
+        &Path::new(ASSETS_DIR).join(&level.config.map),
+        &level.palette,
+    ) {
+        Ok(saved_map) => saved_map,
+        Err(e) => {
+            error!("Couldn't read back saved map: {e}");
+            status.0 = format!("Saved, but couldn't read back the map: {e}");
+            return;
+        }
+    };
+
+    let default_table = EnemyTable::default();
+    let enemy_table = enemies.table().unwrap_or(&default_table);
+    let issues = validate_level(&level.config, &saved_map, enemy_table);
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+
+    status.0 = format!(
+        "Saved with {} error(s) and {} warning(s).",
+        errors,
+        issues.len() - errors
+    );
+    if level.config.map != old_map {
+        status.0 += &format!(
+            "\nThe map is now {}, and the level keeps its spawner numbers and critters.",
+            level.config.map
+        );
+    }
+    if let Some(issue) = issues.first() {
+        status.0 += &format!("\n{issue}");
+    }
+}
+
+/// Writes the level and its map to the assets directory.
+///
+/// Maps in formats that the editor can't write are saved as a `.map.png` next to
+/// the original, and the level is changed to use it.
+fn save_level(level: &mut EditorLevel, map: &mut Map) -> Result<(), ToolError> {
+    let assets = Path::new(ASSETS_DIR);
+
+    if !level.config.map.ends_with(".map.png") && !level.config.map.ends_with(".map.txt") {
+        let stem = Path::new(&level.config.map).with_extension("");
+        level.config.map = format!("{}.map.png", stem.to_string_lossy());
+        move_map_objects(&mut level.config, map);
+    }
+
+    write_map(&assets.join(&level.config.map), map, &level.palette)?;
+
+    let path = assets.join(&level.path);
+    let ron = ron::ser::to_string_pretty(&level.config, PrettyConfig::new().struct_names(true))
+        .map_err(|e| ToolError::Serialize(path.clone(), e))?;
+    std::fs::write(&path, ron).map_err(|e| ToolError::Io(path.clone(), e))
+}
+
+/// Moves the spawner indices and critters of a map into the level, so that the
+/// map can be saved in a format that can't store them. Spawns are renumbered to
+/// keep using the same spawner tiles once the spawners are numbered by position.
+fn move_map_objects(config: &mut LevelConfig, map: &mut Map) {
+    let before = map.spawners();
+    let objects = std::mem::take(&mut map.2);
+    let after = map.spawners();
+
+    // Spawners are listed in the same order either way, only their indices change.
+    let renumbered = before
+        .iter()
+        .zip(&after)
+        .map(|((_, old), (_, new))| (*old, *new))
+        .collect::<HashMap<_, _>>();
+
+    for spawn in config.waves.iter_mut().flat_map(|wave| &mut wave.spawns) {
+        if let Some(new) = renumbered.get(&spawn.spawner) {
+            spawn.spawner = *new;
+        }
+    }
+
+    config.critters.extend(objects.critters);
+}
+
+fn update_status_text(status: Res<EditorStatus>, mut query: Query<&mut Text, With<StatusText>>) {
+    if !status.is_changed() {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.0.clone_from(&status.0);
+    }
+}
+
+fn back(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if interaction_query.iter().any(|i| *i == Interaction::Pressed)
+        || keys.just_pressed(KeyCode::Escape)
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<&TileEntities>) {
+    for entities in &query {
+        for entity in entities.0.iter().flatten() {
+            commands.entity(*entity).despawn();
+        }
+    }
+
+    commands.remove_resource::<EditorLevel>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map_text::map_from_text, tilemap::TilePos};
+
+    #[test]
+    fn moved_spawner_indices_keep_their_spawns() {
+        let (mut map, _) = map_from_text("S = Spawn\n: = Road\n---\nS:S\n").unwrap();
+        map.2.spawners.insert(TilePos { x: 0, y: 0 }, 1);
+        map.2.spawners.insert(TilePos { x: 2, y: 0 }, 0);
+        map.2
+            .critters
+            .push((TilePos { x: 1, y: 0 }, CritterKind::Snake));
+
+        let mut config: LevelConfig = ron::from_str(
+            "LevelConfig(
+                map: \"a.tmj\",
+                workers: 1,
+                currency: Currency(stone: 0, metal: 0, crystal: 0),
+                waves: [Wave(spawns: [
+                    Spawn(spawner: 0, num: 1, delay: 0., interval: 1., hp: 1, kind: Skeleton),
+                    Spawn(spawner: 1, num: 1, delay: 0., interval: 1., hp: 1, kind: Ent),
+                ])],
+                critters: [],
+            )",
+        )
+        .unwrap();
+
+        let spawner_pos = |map: &Map, index: usize| {
+            map.spawners()
+                .into_iter()
+                .find(|(_, i)| *i == index)
+                .map(|(pos, _)| pos)
+        };
+        let before = config.waves[0]
+            .spawns
+            .iter()
+            .map(|spawn| spawner_pos(&map, spawn.spawner))
+            .collect::<Vec<_>>();
+
+        move_map_objects(&mut config, &mut map);
+
+        assert!(map.2.spawners.is_empty());
+        let after = config.waves[0]
+            .spawns
+            .iter()
+            .map(|spawn| spawner_pos(&map, spawn.spawner))
+            .collect::<Vec<_>>();
+        assert_eq!(before, after);
+        assert_eq!(config.critters.len(), 1);
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use strum::IntoEnumIterator;
+
+use crate::{
+    enemy::EnemyKind,
+    level::LevelHandle,
+    settings::{DifficultySetting, ProgressSetting},
+    spawner::{Spawn, SpawnTrigger},
+    waves::{Wave, Waves},
+    GameState,
+};
+
+/// How much bigger each generated wave is than the one before it.
+const BUDGET_GROWTH: f32 = 1.12;
+/// How much tougher the enemies in each generated wave are.
+const HP_GROWTH: f32 = 1.05;
+/// How much faster enemies spawn in each generated wave.
+const INTERVAL_DECAY: f32 = 0.96;
+const MIN_INTERVAL: f32 = 0.3;
+/// Generated waves use one more spawner every this many waves.
+const WAVES_PER_SPAWNER: usize = 4;
+/// Generated waves add another kind of enemy to the mix every this many waves.
+const WAVES_PER_ENEMY_KIND: usize = 3;
+const DELAY: f32 = 15.;
+
+pub struct EndlessPlugin;
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_systems(OnEnter(GameState::GameOver), record)
+            .add_systems(OnExit(GameState::GameOver), cleanup);
+    }
+}
+
+#[derive(Resource, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Campaign,
+    /// The level's waves are followed by generated waves that keep getting
+    /// harder until the player loses.
+    Endless,
+}
+
+/// Generates the waves that follow a level's own waves in endless mode.
+pub struct EndlessWaves {
+    /// The spawner indices of the map.
+    spawners: Vec<usize>,
+    /// The total hit points of the enemies in the level's last wave.
+    base_budget: f32,
+    base_hp: f32,
+    base_interval: f32,
+    rng: SmallRng,
+    /// The generated wave being played, if the level's own waves are done.
+    pub wave: Option<Wave>,
+}
+impl EndlessWaves {
+    pub fn new(waves: &[Wave], mut spawners: Vec<usize>) -> Self {
+        spawners.sort_unstable();
+        spawners.dedup();
+
+        let last = waves
+            .last()
+            .map(|wave| wave.spawns.as_slice())
+            .unwrap_or_default();
+
+        let base_budget = last.iter().map(|s| s.num as f32 * s.hp as f32).sum::<f32>();
+        let base_hp = last.iter().map(|s| s.hp).max().unwrap_or(2) as f32;
+        let base_interval = last
+            .iter()
+            .map(|s| s.interval)
+            .reduce(f32::min)
+            .unwrap_or(2.);
+
+        Self {
+            spawners,
+            base_budget: base_budget.max(20.),
+            base_hp,
+            base_interval,
+            rng: SmallRng::from_entropy(),
+            wave: None,
+        }
+    }
+
+    /// Generates the `number`th wave after the level's own waves, counting from 1.
+    pub fn generate(&mut self, number: usize) {
+        let exponent = number as i32;
+
+        let budget = self.base_budget * BUDGET_GROWTH.powi(exponent);
+        let hp = (self.base_hp * HP_GROWTH.powi(exponent)).round().max(1.) as u32;
+        let interval = (self.base_interval * INTERVAL_DECAY.powi(exponent)).max(MIN_INTERVAL);
+
+        let kinds = EnemyKind::iter()
+            .take(1 + number / WAVES_PER_ENEMY_KIND)
+            .collect::<Vec<_>>();
+
+        let mut spawners = self.spawners.clone();
+        spawners.shuffle(&mut self.rng);
+        spawners.truncate(1 + number / WAVES_PER_SPAWNER);
+
+        let num = ((budget / hp as f32 / spawners.len().max(1) as f32).round() as usize).max(1);
+
+        let spawns = spawners
+            .into_iter()
+            .map(|spawner| Spawn {
+                spawner,
+                num,
+                delay: DELAY + self.rng.gen_range(0. ..interval * 4.),
+                interval,
+                hp,
+                kind: *kinds.choose(&mut self.rng).unwrap_or(&EnemyKind::Skeleton),
+                speed: None,
+                damage: None,
+                reward: None,
+                group: None,
+                trigger: SpawnTrigger::WaveStart,
+            })
+            .collect();
+
+        self.wave = Some(Wave {
+            spawns,
+            timeout: None,
+        });
+    }
+}
+
+/// How far the player got in endless mode.
+#[derive(Resource, Debug)]
+pub struct EndlessResult {
+    pub wave: usize,
+    /// The best wave reached on the level on this difficulty before this run.
+    pub previous_best: Option<usize>,
+}
+
+pub fn record(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    waves: Res<Waves>,
+    difficulty: Res<DifficultySetting>,
+    level_handle: Res<LevelHandle>,
+    asset_server: Res<AssetServer>,
+    mut progress: ResMut<ProgressSetting>,
+) {
+    if *mode != GameMode::Endless {
+        commands.remove_resource::<EndlessResult>();
+        return;
+    }
+
+    let wave = waves.current + 1;
+
+    let Some(key) = asset_server
+        .get_path(level_handle.0.id())
+        .map(|path| path.to_string())
+    else {
+        warn!("Couldn't find level path when saving endless high score.");
+        return;
+    };
+
+    let level_progress = progress.levels.entry(key).or_default();
+    let previous_best = level_progress.best_wave.get(&*difficulty).copied();
+    if previous_best.is_none_or(|best| wave > best) {
+        level_progress.best_wave.insert(difficulty.clone(), wave);
+    }
+
+    commands.insert_resource(EndlessResult {
+        wave,
+        previous_best,
+    });
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<EndlessResult>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endless() -> EndlessWaves {
+        let last = Wave {
+            spawns: vec![Spawn {
+                spawner: 0,
+                num: 10,
+                delay: 0.,
+                interval: 2.,
+                hp: 4,
+                kind: EnemyKind::Skeleton,
+                speed: None,
+                damage: None,
+                reward: None,
+                group: None,
+                trigger: SpawnTrigger::WaveStart,
+            }],
+            timeout: None,
+        };
+
+        EndlessWaves::new(&[last], vec![2, 0, 1, 0])
+    }
+
+    fn generate(endless: &mut EndlessWaves, number: usize) -> Wave {
+        endless.generate(number);
+        endless.wave.clone().unwrap()
+    }
+
+    fn total_hp(wave: &Wave) -> u32 {
+        wave.spawns.iter().map(|s| s.num as u32 * s.hp).sum()
+    }
+
+    #[test]
+    fn waves_get_harder() {
+        let mut endless = endless();
+
+        let first = generate(&mut endless, 1);
+        let later = generate(&mut endless, 10);
+
+        assert!(total_hp(&later) > total_hp(&first));
+        assert!(later.spawns[0].hp > first.spawns[0].hp);
+        assert!(later.spawns[0].interval < first.spawns[0].interval);
+        assert!(generate(&mut endless, 200).spawns[0].interval >= MIN_INTERVAL);
+    }
+
+    #[test]
+    fn spawners_are_added_over_time() {
+        let mut endless = endless();
+
+        for (number, expected) in [(1, 1), (4, 2), (8, 3), (40, 3)] {
+            let mut spawners = generate(&mut endless, number)
+                .spawns
+                .iter()
+                .map(|s| s.spawner)
+                .collect::<Vec<_>>();
+            spawners.sort_unstable();
+            spawners.dedup();
+
+            assert_eq!(spawners.len(), expected, "wave {number}");
+            assert!(spawners.iter().all(|s| *s <= 2), "wave {number}");
+        }
+    }
+
+    #[test]
+    fn enemy_kinds_are_added_over_time() {
+        let mut endless = endless();
+        let first_kind = EnemyKind::iter().next().unwrap();
+
+        for _ in 0..10 {
+            let wave = generate(&mut endless, 2);
+            assert!(wave.spawns.iter().all(|s| s.kind == first_kind));
+        }
+    }
+
+    #[test]
+    fn levels_without_waves_still_get_enemies() {
+        let mut endless = EndlessWaves::new(&[], vec![0]);
+
+        let wave = generate(&mut endless, 1);
+
+        assert_eq!(wave.spawns.len(), 1);
+        assert!(total_hp(&wave) >= 20);
+    }
+}
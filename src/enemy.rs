@@ -1,20 +1,27 @@
-use bevy::prelude::*;
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
 use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
 
 use crate::{
+    currency::Currency,
+    damage::{apply_damage, Armor, DamageEvent, DamageType, DamagedEvent},
+    enemy_table::Enemies,
     hit_points::HitPoints,
     home::Home,
-    movement::{MovingProgress, Speed},
-    particle::ParticleKind,
-    pathfinding::{enemy_cost_fn, heuristic, NeighborCostIter, PathState},
+    movement::{self, MovingProgress, Speed},
+    pathfinding::{enemy_cost_fn, heuristic, update_flow_fields, FlowFields, PathState, NEIGHBORS},
     settings::{DifficultySetting, ParticlesSetting},
     stats::Stats,
-    tilemap::{AtlasHandle, Map, TilePos},
+    tilemap::{AtlasHandle, Map, MapChangedEvent, TileEntities, TilePos},
+    tower::Tower,
     util::cleanup,
+    worker::Worker,
     GameState,
 };
-use pathfinding::prelude::astar;
 
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
@@ -23,14 +30,28 @@ impl Plugin for EnemyPlugin {
             .init_resource::<EnemyRng>()
             .add_systems(
                 Update,
-                (spawn, pathfinding, behavior, tick_cooldown, attack, die)
+                (
+                    spawn,
+                    reroute.before(movement::movement),
+                    // Picking the next tile right after movement keeps enemies from
+                    // pausing for a frame on every tile.
+                    pathfinding
+                        .after(update_flow_fields)
+                        .after(movement::movement),
+                    tick_cooldown,
+                    (attack_workers.before(attack), attack_towers, attack).before(apply_damage),
+                    show_hits.after(apply_damage),
+                    die.after(show_hits),
+                )
                     .run_if(in_state(GameState::Playing)),
             )
             .add_systems(OnExit(GameState::GameOver), cleanup::<EnemyKind>);
     }
 }
 
-#[derive(Component, Default, Deserialize, Copy, Clone)]
+#[derive(
+    Component, Default, Serialize, Deserialize, EnumIter, Debug, Copy, Clone, PartialEq, Eq, Hash,
+)]
 #[require(
     Sprite,
     HitPoints,
@@ -38,6 +59,8 @@ impl Plugin for EnemyPlugin {
     MovingProgress,
     Speed,
     AttackCooldown,
+    AttackDamage,
+    Armor,
     Behavior
 )]
 pub enum EnemyKind {
@@ -51,40 +74,48 @@ pub enum EnemyKind {
     EntThree,
     EntFour,
 }
-impl EnemyKind {
-    pub fn atlas_index(&self) -> usize {
-        match self {
-            Self::Skeleton => 103 * 9 + 36,
-            Self::SkeletonTwo => 103 * 9 + 37,
-            Self::SkeletonThree => 103 * 9 + 38,
-            Self::SkeletonFour => 103 * 9 + 42,
-            Self::Ent => 103 * 15 + 45,
-            Self::EntTwo => 103 * 15 + 52,
-            Self::EntThree => 103 * 15 + 53,
-            Self::EntFour => 103 * 15 + 54,
-        }
-    }
-}
-
 #[derive(Event)]
 pub struct SpawnEnemyEvent {
     pub kind: EnemyKind,
     pub pos: TilePos,
     pub hp: u32,
+    pub speed: Option<f32>,
+    pub damage: Option<u32>,
+    pub reward: Option<Currency>,
+}
+
+/// Physical damage dealt by each of the enemy's attacks.
+#[derive(Component)]
+pub struct AttackDamage(pub u32);
+impl Default for AttackDamage {
+    fn default() -> Self {
+        Self(1)
+    }
 }
 
+/// Given to the player when the enemy is defeated.
+#[derive(Component)]
+pub struct Reward(pub Currency);
+
 #[derive(Component, Default)]
 enum Behavior {
     #[default]
     SeekHome,
     Attack,
+    /// Attacking a tower next to the enemy's path.
+    AttackTower(Entity),
 }
 
 #[derive(Component)]
 pub struct AttackCooldown(Timer);
+impl AttackCooldown {
+    pub fn new(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
 impl Default for AttackCooldown {
     fn default() -> Self {
-        Self(Timer::from_seconds(1., TimerMode::Once))
+        Self::new(1.)
     }
 }
 
@@ -103,6 +134,7 @@ fn spawn(
     atlas_handle: Res<AtlasHandle>,
     tilemap_query: Query<&Map>,
     difficulty: Res<DifficultySetting>,
+    enemies: Enemies,
 ) {
     for event in events.read() {
         let Ok(tilemap) = tilemap_query.single() else {
@@ -111,18 +143,15 @@ fn spawn(
 
         let world = tilemap.pos_to_world(event.pos);
 
-        let hp = match *difficulty {
-            DifficultySetting::Hard => event.hp,
-            DifficultySetting::Normal => ((event.hp as f32 * 0.75).floor() as u32).max(1),
-            DifficultySetting::Impossible => ((event.hp as f32 * 1.25).floor() as u32).max(1),
-        };
+        let stats = enemies.stats(event.kind);
+        let hp = difficulty.scale_hp(stats.hp(event.hp));
 
-        commands.spawn((
+        let mut entity = commands.spawn((
             Sprite {
                 image: atlas_handle.image.clone(),
                 texture_atlas: Some(TextureAtlas {
                     layout: atlas_handle.layout.clone(),
-                    index: event.kind.atlas_index(),
+                    index: stats.sprite,
                 }),
                 ..default()
             },
@@ -134,149 +163,289 @@ fn spawn(
             HitPoints::full(hp),
             event.kind,
             event.pos,
-            Speed(2.),
+            Speed(event.speed.unwrap_or(stats.speed)),
+            AttackDamage(event.damage.unwrap_or(stats.damage)),
+            AttackCooldown::new(stats.attack_cooldown()),
+            Armor::new(stats.armor, stats.resistances),
             Name::new("Enemy"),
         ));
+
+        if let Some(reward) = &event.reward {
+            entity.insert(Reward(reward.clone()));
+        }
     }
 }
 
+/// Moves enemies one tile at a time along the flow field of their cost class, so
+/// that they retarget as soon as the field changes.
 fn pathfinding(
     mut commands: Commands,
-    query: Query<(Entity, &TilePos, &Behavior, &EnemyKind), Without<PathState>>,
-    tilemap_query: Query<&Map>,
-    home_query: Query<(&TilePos, &HitPoints), With<Home>>,
+    mut query: Query<(Entity, &TilePos, &mut Behavior, &EnemyKind), Without<PathState>>,
+    flow_fields: Res<FlowFields>,
+    enemies: Enemies,
+    tilemap_query: Query<&TileEntities>,
+    tower_query: Query<&HitPoints, With<Tower>>,
     mut rng: ResMut<EnemyRng>,
 ) {
-    // TODO spawner should do the pathfinding and cache the result.
-
-    for (entity, pos, behavior, kind) in &query {
-        if !matches!(behavior, Behavior::SeekHome) {
+    for (entity, pos, mut behavior, kind) in &mut query {
+        if !matches!(*behavior, Behavior::SeekHome) {
             continue;
         }
 
-        let Ok(map) = tilemap_query.single() else {
-            return;
-        };
+        let stats = enemies.stats(*kind);
 
-        let goals = home_query
-            .iter()
-            .filter(|(_, hp)| !hp.is_zero())
-            .collect::<Vec<_>>();
-        let Some((goal, _)) = goals.choose(&mut rng.0) else {
-            return;
+        let Some(field) = flow_fields.get(stats.terrain) else {
+            continue;
         };
 
-        // choose a random neighbor of the goal and path directly to it,
-        // so when enemies are attacking it feels a bit swarmier.
-        let neighbors =
-            NeighborCostIter::new(**goal, enemy_cost_fn(map, *kind)).collect::<Vec<_>>();
-        let Some((goal, _)) = neighbors.choose(&mut rng.0) else {
-            return;
-        };
+        if field.cost(*pos) == Some(0) {
+            *behavior = Behavior::Attack;
+            continue;
+        }
 
-        let Some(result) = astar(
-            pos,
-            |p| NeighborCostIter::new(*p, enemy_cost_fn(map, *kind)),
-            |p| heuristic(*p, *goal),
-            |p| *p == *goal,
-        ) else {
-            warn!("Enemy unable to find path to goal.");
+        if stats.attacks_towers {
+            let tower = tilemap_query.single().ok().and_then(|tile_entities| {
+                NEIGHBORS
+                    .iter()
+                    .map(|offset| TilePos::from(*offset + *pos))
+                    .filter_map(|pos| tile_entities.0.get(pos.y, pos.x).copied().flatten())
+                    .find(|entity| tower_query.get(*entity).is_ok_and(|hp| !hp.is_zero()))
+            });
+
+            if let Some(tower) = tower {
+                *behavior = Behavior::AttackTower(tower);
+                continue;
+            }
+        }
+
+        // choose randomly between equally good tiles, so that enemies spread
+        // out a bit and it feels swarmier.
+        let Some(next) = field.next(*pos).choose(&mut rng.0).copied() else {
+            // No home can be reached right now. The field is rebuilt when the map
+            // changes, which may open up a path.
             continue;
         };
 
         // The enemy may have died and been despawned in the same frame.
         commands
             .entity(entity)
-            .try_insert(PathState::from(result.0));
-
-        // limit the amount of pathfinding we do each frame.
-        break;
+            .try_insert(PathState::from(vec![*pos, next]));
     }
 }
 
-fn behavior(
-    mut removed: RemovedComponents<PathState>,
-    mut query: Query<&mut Behavior, Without<PathState>>,
+/// Stops enemies that are about to walk onto a tile that can no longer be walked
+/// on, so that they pick a new way from their current tile.
+fn reroute(
+    mut commands: Commands,
+    mut events: EventReader<MapChangedEvent>,
+    mut query: Query<(
+        Entity,
+        &PathState,
+        &TilePos,
+        &EnemyKind,
+        &mut MovingProgress,
+        &mut Transform,
+    )>,
+    tilemap_query: Query<&Map>,
+    enemies: Enemies,
 ) {
-    // just assume that we've reached the home whenever a PathState is removed.
+    if events.is_empty() {
+        return;
+    }
 
-    let mut enemy_iter = query.iter_many_mut(removed.read());
-    while let Some(mut behavior) = enemy_iter.fetch_next() {
-        if matches!(*behavior, Behavior::SeekHome) {
-            *behavior = Behavior::Attack;
+    let changed = events.read().map(|event| event.pos).collect::<HashSet<_>>();
+
+    let Ok(map) = tilemap_query.single() else {
+        return;
+    };
+
+    for (entity, path_state, pos, kind, mut progress, mut transform) in &mut query {
+        let cost_fn = enemy_cost_fn(map, enemies.stats(*kind).terrain);
+
+        let blocked = path_state.path[path_state.index + 1..]
+            .iter()
+            .any(|p| changed.contains(p) && cost_fn((p.x as isize, p.y as isize)) == -1);
+        if !blocked {
+            continue;
         }
+
+        // Step back to the middle of the current tile.
+        let world = map.pos_to_world(*pos);
+        transform.translation.x = world.x;
+        transform.translation.y = world.y;
+        progress.reset();
+
+        commands.entity(entity).remove::<PathState>();
     }
 }
 
-fn attack(
-    mut commands: Commands,
-    mut query: Query<(Entity, &Behavior, &mut AttackCooldown, &TilePos), Without<PathState>>,
-    mut home_query: Query<(&mut HitPoints, &TilePos), With<Home>>,
-    tilemap_query: Query<&Map>,
-    particle_settings: Res<ParticlesSetting>,
+/// Enemies strike workers on their own tile or a neighboring one whenever their
+/// attack is ready, without stopping on their way to a home.
+fn attack_workers(
+    mut query: Query<(&mut AttackCooldown, &AttackDamage, &TilePos), With<EnemyKind>>,
+    worker_query: Query<(Entity, &HitPoints, &TilePos), With<Worker>>,
+    mut damage_writer: EventWriter<DamageEvent>,
 ) {
-    for (entity, behavior, mut cooldown, pos) in &mut query {
-        if !matches!(behavior, Behavior::Attack) {
-            continue;
+    if worker_query.is_empty() {
+        return;
+    }
+
+    let mut workers: HashMap<TilePos, Vec<Entity>> = HashMap::default();
+    for (entity, hp, pos) in &worker_query {
+        if !hp.is_zero() {
+            workers.entry(*pos).or_default().push(entity);
         }
+    }
 
+    for (mut cooldown, damage, pos) in &mut query {
         if !cooldown.0.finished() {
             continue;
         }
 
-        let Some((mut home_hp, home_pos)) = home_query
-            .iter_mut()
-            .find(|(_, home_pos)| heuristic(**home_pos, *pos) == 1)
+        let Some(target) = std::iter::once(*pos)
+            .chain(NEIGHBORS.iter().map(|offset| TilePos::from(*offset + *pos)))
+            .filter_map(|pos| workers.get(&pos))
+            .flatten()
+            .next()
         else {
-            warn!("Enemy could not locate a nearby home.");
             continue;
         };
 
-        if home_hp.is_zero() {
+        damage_writer.write(DamageEvent {
+            target: *target,
+            amount: damage.0,
+            kind: DamageType::Physical,
+        });
+
+        cooldown.0.reset();
+    }
+}
+
+fn attack_towers(
+    mut query: Query<
+        (&mut Behavior, &mut AttackCooldown, &AttackDamage),
+        (With<EnemyKind>, Without<PathState>),
+    >,
+    tower_query: Query<&HitPoints, With<Tower>>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for (mut behavior, mut cooldown, damage) in &mut query {
+        let Behavior::AttackTower(target) = *behavior else {
+            continue;
+        };
+
+        // The tower was destroyed, so carry on towards the homes.
+        if !tower_query.get(target).is_ok_and(|hp| !hp.is_zero()) {
+            *behavior = Behavior::SeekHome;
+            continue;
+        }
+
+        if !cooldown.0.finished() {
+            continue;
+        }
+
+        damage_writer.write(DamageEvent {
+            target,
+            amount: damage.0,
+            kind: DamageType::Physical,
+        });
+
+        cooldown.0.reset();
+    }
+}
+
+fn attack(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &Behavior,
+            &mut AttackCooldown,
+            &AttackDamage,
+            &TilePos,
+        ),
+        Without<PathState>,
+    >,
+    home_query: Query<(Entity, &HitPoints, &TilePos), With<Home>>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for (entity, behavior, mut cooldown, damage, pos) in &mut query {
+        if !matches!(behavior, Behavior::Attack) {
+            continue;
+        }
+
+        let Some((home, ..)) = home_query
+            .iter()
+            .find(|(_, hp, home_pos)| !hp.is_zero() && heuristic(**home_pos, *pos) == 1)
+        else {
+            // The home was destroyed, so follow the flow field to the next one.
             // Enemy may have died and been despawned
             commands.entity(entity).try_insert(Behavior::SeekHome);
             continue;
+        };
+
+        if !cooldown.0.finished() {
+            continue;
         }
 
-        let Ok(map) = tilemap_query.single() else {
+        damage_writer.write(DamageEvent {
+            target: home,
+            amount: damage.0,
+            kind: DamageType::Physical,
+        });
+
+        cooldown.0.reset();
+    }
+}
+
+/// Bursts enemies into particles of the damage that hit them.
+fn show_hits(
+    mut commands: Commands,
+    mut events: EventReader<DamagedEvent>,
+    query: Query<(&Transform, &EnemyKind)>,
+    particle_settings: Res<ParticlesSetting>,
+    enemies: Enemies,
+) {
+    for event in events.read() {
+        let Ok((transform, kind)) = query.get(event.target) else {
             continue;
         };
 
-        home_hp.sub(1);
-
-        let amt = if home_hp.is_zero() {
-            particle_settings.kill_amt()
+        let amt = if event.killed {
+            particle_settings.kill_amt() / 2
         } else {
-            particle_settings.hit_amt()
+            particle_settings.hit_amt() / 2
         };
         for _ in 0..amt {
             commands.spawn((
-                ParticleKind::Home,
-                Transform::from_translation(map.pos_to_world(*home_pos).extend(0.)),
+                enemies.stats(*kind).particle,
+                Transform::from_translation(transform.translation),
+            ));
+            commands.spawn((
+                event.kind.particle(),
+                Transform::from_translation(transform.translation),
             ));
         }
-
-        if home_hp.is_zero() {
-            // Enemy may have died and been despawned
-            commands.entity(entity).try_insert(Behavior::SeekHome);
-        }
-
-        cooldown.0.reset();
     }
 }
 
 fn die(
     mut commands: Commands,
-    query: Query<(Entity, &HitPoints), With<EnemyKind>>,
+    query: Query<(Entity, &HitPoints, Option<&Reward>), With<EnemyKind>>,
     mut stats: ResMut<Stats>,
+    mut currency: ResMut<Currency>,
 ) {
-    for (entity, hp) in &query {
+    for (entity, hp, reward) in &query {
         if !hp.is_zero() {
             continue;
         }
 
         stats.kills += 1;
 
+        if let Some(reward) = reward {
+            currency.add(&reward.0);
+        }
+
         commands.entity(entity).despawn();
     }
 }
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_common_assets::ron::RonAssetPlugin;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    damage::Resistances, enemy::EnemyKind, loading::LoadingAssets, particle::ParticleKind,
+    pathfinding::EnemyCostClass, GameState,
+};
+
+/// The stats of every kind of enemy.
+pub const ENEMY_TABLE: &str = "default.enemies.ron";
+
+/// Used for enemy kinds that are missing from the table.
+const FALLBACK: EnemyStats = EnemyStats {
+    hp_multiplier: 1.,
+    speed: 2.,
+    damage: 1,
+    attack_rate: 1.,
+    terrain: EnemyCostClass::Ground,
+    particle: ParticleKind::Bone,
+    sprite: 103 * 9 + 36,
+    attacks_towers: false,
+    armor: 0.,
+    resistances: Resistances::NONE,
+};
+
+pub struct EnemyTablePlugin;
+impl Plugin for EnemyTablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<EnemyTable>::new(&["enemies.ron"]))
+            .add_systems(OnEnter(GameState::Loading), queue_load)
+            .add_systems(Update, check_kinds);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyStats {
+    /// Multiplies the hit points that waves give this kind of enemy.
+    pub hp_multiplier: f32,
+    /// In tile lengths per second. Spawns may override this.
+    pub speed: f32,
+    /// Damage dealt by each attack on a home, worker or tower. Spawns may override
+    /// this.
+    pub damage: u32,
+    /// Attacks per second.
+    pub attack_rate: f32,
+    /// Which tiles the enemy can walk on.
+    pub terrain: EnemyCostClass,
+    /// Given off when the enemy is hit by a bullet.
+    pub particle: ParticleKind,
+    /// Index of the enemy's sprite in the tileset.
+    pub sprite: usize,
+    /// Whether the enemy stops to attack towers next to its path.
+    #[serde(default)]
+    pub attacks_towers: bool,
+    /// Subtracted from the physical damage of each hit the enemy takes.
+    #[serde(default)]
+    pub armor: f32,
+    #[serde(default)]
+    pub resistances: Resistances,
+}
+impl EnemyStats {
+    /// The hit points of an enemy that a wave gives `hp`, before difficulty scaling.
+    pub fn hp(&self, hp: u32) -> u32 {
+        ((hp as f32 * self.hp_multiplier).round() as u32).max(1)
+    }
+
+    pub fn attack_cooldown(&self) -> f32 {
+        1. / self.attack_rate.max(0.01)
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct EnemyTable(pub HashMap<EnemyKind, EnemyStats>);
+impl EnemyTable {
+    pub fn stats(&self, kind: EnemyKind) -> &EnemyStats {
+        self.0.get(&kind).unwrap_or(&FALLBACK)
+    }
+
+    pub fn missing(&self) -> Vec<EnemyKind> {
+        EnemyKind::iter()
+            .filter(|kind| !self.0.contains_key(kind))
+            .collect()
+    }
+}
+
+#[derive(Resource)]
+pub struct EnemyTableHandle(pub Handle<EnemyTable>);
+
+/// Looks up enemy stats in the loaded [`EnemyTable`].
+#[derive(SystemParam)]
+pub struct Enemies<'w> {
+    handle: Option<Res<'w, EnemyTableHandle>>,
+    tables: Res<'w, Assets<EnemyTable>>,
+}
+impl Enemies<'_> {
+    pub fn table(&self) -> Option<&EnemyTable> {
+        self.tables.get(&self.handle.as_ref()?.0)
+    }
+
+    pub fn stats(&self, kind: EnemyKind) -> &EnemyStats {
+        self.table().map_or(&FALLBACK, |table| table.stats(kind))
+    }
+}
+
+fn queue_load(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+) {
+    let handle = asset_server.load(ENEMY_TABLE);
+    loading_assets.0.push(handle.id().into());
+    commands.insert_resource(EnemyTableHandle(handle));
+}
+
+fn check_kinds(mut events: EventReader<AssetEvent<EnemyTable>>, tables: Res<Assets<EnemyTable>>) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        let Some(table) = tables.get(*id) else {
+            continue;
+        };
+
+        for kind in table.missing() {
+            warn!("Enemy table has no stats for {kind:?}.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(hp_multiplier: f32) -> EnemyStats {
+        EnemyStats {
+            hp_multiplier,
+            ..FALLBACK
+        }
+    }
+
+    #[test]
+    fn hp_is_multiplied_and_rounded() {
+        assert_eq!(stats(1.).hp(4), 4);
+        assert_eq!(stats(1.5).hp(3), 5);
+        assert_eq!(stats(0.5).hp(5), 3);
+        assert_eq!(stats(0.4).hp(4), 2);
+    }
+
+    #[test]
+    fn hp_is_at_least_one() {
+        assert_eq!(stats(0.1).hp(3), 1);
+        assert_eq!(stats(0.).hp(10), 1);
+        assert_eq!(stats(1.).hp(0), 1);
+    }
+
+    #[test]
+    fn default_table_has_every_kind() {
+        let table: EnemyTable =
+            ron::from_str(include_str!("../assets/default.enemies.ron")).unwrap();
+
+        assert!(table.missing().is_empty(), "{:?}", table.missing());
+    }
+}
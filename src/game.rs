@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
 use crate::{
-    enemy::EnemyKind, hit_points::HitPoints, home::Home, spawner::SpawnerStates, waves::Waves,
+    hit_points::HitPoints,
+    home::Home,
+    objective::{self, Objectives},
     GameState,
 };
 
@@ -10,7 +12,9 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Won>().add_systems(
             Update,
-            (check_win, check_loss).run_if(in_state(GameState::Playing)),
+            (check_win, check_loss)
+                .after(objective::update)
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
@@ -19,21 +23,11 @@ impl Plugin for GamePlugin {
 pub struct Won(pub bool);
 
 fn check_win(
-    waves: Res<Waves>,
-    spawners: Res<SpawnerStates>,
-    enemies: Query<(), With<EnemyKind>>,
+    objectives: Res<Objectives>,
     mut won: ResMut<Won>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if waves.current().is_some() {
-        return;
-    }
-
-    if spawners.states.iter().any(|s| s.remaining > 0) {
-        return;
-    }
-
-    if enemies.iter().len() > 0 {
+    if !objectives.complete() {
         return;
     }
 
@@ -45,14 +39,13 @@ fn check_win(
 fn check_loss(
     changed: Query<(), (With<Home>, Changed<HitPoints>)>,
     query: Query<&HitPoints, With<Home>>,
+    objectives: Res<Objectives>,
     mut won: ResMut<Won>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if changed.iter().count() == 0 {
-        return;
-    }
+    let homes_destroyed = changed.iter().count() > 0 && query.iter().all(|hp| hp.is_zero());
 
-    if query.iter().any(|hp| !hp.is_zero()) {
+    if !homes_destroyed && !objectives.failed() {
         return;
     }
 
@@ -1,7 +1,11 @@
 use bevy::prelude::*;
 
 use crate::{
+    endless::{self, EndlessResult},
     game::Won,
+    hud::{OBJECTIVE_COMPLETE, OBJECTIVE_FAILED},
+    objective::{ObjectiveStatus, Objectives},
+    score::{self, Score},
     settings::DifficultySetting,
     stats::Stats,
     ui::{slice_image_mode, UiAssets, BUTTON_TEXT, TITLE_TEXT},
@@ -11,7 +15,10 @@ use crate::{
 pub struct GameOverPlugin;
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameOver), init);
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            init.after(score::record).after(endless::record),
+        );
         app.add_systems(Update, menu_button.run_if(in_state(GameState::GameOver)));
     }
 }
@@ -25,6 +32,9 @@ fn init(
     won: Res<Won>,
     stats: Res<Stats>,
     difficulty: Res<DifficultySetting>,
+    objectives: Res<Objectives>,
+    score: Option<Res<Score>>,
+    endless: Option<Res<EndlessResult>>,
 ) {
     let button_style = Node {
         width: Val::Px(250.0),
@@ -121,6 +131,26 @@ fn init(
         ))
         .id();
 
+    let damage_label = commands
+        .spawn((Text::new("Damage Dealt"), title_text_style.clone()))
+        .id();
+    let damage = commands
+        .spawn((
+            Text::new(format!("{}", stats.damage_dealt)),
+            title_text_style.clone(),
+        ))
+        .id();
+
+    let workers_lost_label = commands
+        .spawn((Text::new("Workers Lost"), title_text_style.clone()))
+        .id();
+    let workers_lost = commands
+        .spawn((
+            Text::new(format!("{}", stats.workers_lost)),
+            title_text_style.clone(),
+        ))
+        .id();
+
     let difficulty_label = commands
         .spawn((Text::new("Difficulty"), title_text_style.clone()))
         .id();
@@ -131,6 +161,42 @@ fn init(
         ))
         .id();
 
+    let objectives_container = commands
+        .spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: vec![GridTrack::flex(1.0), GridTrack::min_content()],
+            row_gap: Val::Px(5.),
+            column_gap: Val::Px(40.),
+            ..default()
+        })
+        .id();
+
+    for objective in objectives.required.iter().chain(objectives.bonus.iter()) {
+        let (status, color) = match objective.status {
+            ObjectiveStatus::InProgress => ("Incomplete", TITLE_TEXT),
+            ObjectiveStatus::Complete => ("Complete", OBJECTIVE_COMPLETE),
+            ObjectiveStatus::Failed => ("Failed", OBJECTIVE_FAILED),
+        };
+
+        let label = commands
+            .spawn((
+                Text::new(objective.objective.description()),
+                title_text_style.clone(),
+            ))
+            .id();
+        let value = commands
+            .spawn((
+                Text::new(status),
+                title_text_style.0.clone(),
+                TextColor(color),
+            ))
+            .id();
+
+        commands
+            .entity(objectives_container)
+            .add_children(&[label, value]);
+    }
+
     let play_button = commands
         .spawn((
             Button,
@@ -152,15 +218,89 @@ fn init(
         difficulty,
         kills_label,
         kills,
+        damage_label,
+        damage,
         mined_label,
         mined,
         built_label,
         built,
+        workers_lost_label,
+        workers_lost,
     ]);
 
     commands
         .entity(container)
-        .add_children(&[title, stats_container, play_button]);
+        .add_children(&[title, stats_container, objectives_container]);
+
+    if let Some(score) = score {
+        let score_container = commands
+            .spawn(Node {
+                display: Display::Grid,
+                grid_template_columns: vec![GridTrack::flex(1.0), GridTrack::min_content()],
+                row_gap: Val::Px(5.),
+                column_gap: Val::Px(40.),
+                ..default()
+            })
+            .id();
+
+        let rows = [
+            ("Home Hit Points", format!("{}", score.home_hit_points)),
+            ("Time", format!("{}", score.time)),
+            ("Resources Left", format!("{}", score.resources)),
+            ("Bonus Points", format!("{}", score.bonus)),
+            ("Difficulty Multiplier", format!("x{}", score.multiplier)),
+            ("Score", format!("{}", score.total)),
+        ];
+
+        for (label, value) in rows {
+            let label = commands
+                .spawn((Text::new(label), title_text_style.clone()))
+                .id();
+            let value = commands
+                .spawn((Text::new(value), title_text_style.clone()))
+                .id();
+
+            commands
+                .entity(score_container)
+                .add_children(&[label, value]);
+        }
+
+        let mut summary = vec![];
+        if score.has_stars {
+            summary.push(format!("{}/3 stars", score.stars));
+        }
+        match score.previous_best {
+            _ if score.is_personal_best() => summary.push("New personal best!".to_string()),
+            Some(best) => summary.push(format!("Personal best: {}", best.score)),
+            None => {}
+        }
+
+        let summary = commands
+            .spawn((Text::new(summary.join("  ")), title_text_style.clone()))
+            .id();
+
+        commands
+            .entity(container)
+            .add_children(&[score_container, summary]);
+    }
+
+    if let Some(endless) = endless {
+        let mut text = format!("You reached wave {}!", endless.wave);
+        match endless.previous_best {
+            Some(best) if best >= endless.wave => {
+                text += &format!("  Best wave: {best}");
+            }
+            _ => text += "  New best wave!",
+        }
+
+        let summary = commands
+            .spawn((Text::new(text), title_text_style.clone()))
+            .id();
+
+        commands.entity(container).add_child(summary);
+    }
+
+    commands.entity(container).add_child(play_button);
 }
 
 fn menu_button(
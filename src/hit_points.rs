@@ -20,9 +20,15 @@ impl HitPoints {
     pub fn sub(&mut self, val: u32) {
         self.current = self.current.saturating_sub(val);
     }
+    pub fn add(&mut self, val: u32) {
+        self.current = (self.current + val).min(self.max);
+    }
     pub fn is_zero(&self) -> bool {
         self.current == 0
     }
+    pub fn is_full(&self) -> bool {
+        self.current >= self.max
+    }
     pub fn fraction(&self) -> f32 {
         self.current as f32 / self.max as f32
     }
@@ -1,11 +1,21 @@
 use bevy::prelude::*;
 
-use crate::{hit_points::HitPoints, tilemap::TileKind, GameState};
+use crate::{
+    damage::{apply_damage, DamagedEvent},
+    hit_points::HitPoints,
+    particle::ParticleKind,
+    settings::ParticlesSetting,
+    tilemap::TileKind,
+    GameState,
+};
 
 pub struct HomePlugin;
 impl Plugin for HomePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_sprites.run_if(in_state(GameState::Playing)));
+        app.add_systems(
+            Update,
+            (update_sprites, show_hits.after(apply_damage)).run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
@@ -21,3 +31,28 @@ fn update_sprites(mut query: Query<(&mut Sprite, &HitPoints), (Changed<HitPoints
         }
     }
 }
+
+fn show_hits(
+    mut commands: Commands,
+    mut events: EventReader<DamagedEvent>,
+    query: Query<&Transform, With<Home>>,
+    particle_settings: Res<ParticlesSetting>,
+) {
+    for event in events.read() {
+        let Ok(transform) = query.get(event.target) else {
+            continue;
+        };
+
+        let amt = if event.killed {
+            particle_settings.kill_amt()
+        } else {
+            particle_settings.hit_amt()
+        };
+        for _ in 0..amt {
+            commands.spawn((
+                ParticleKind::Home,
+                Transform::from_translation(transform.translation.truncate().extend(0.)),
+            ));
+        }
+    }
+}
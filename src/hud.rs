@@ -10,14 +10,20 @@ use crate::{
     designate_tool::DesignationKind,
     hit_points::HitPoints,
     home::Home,
+    level::LevelReloadedEvent,
+    objective::{ObjectiveProgress, ObjectiveStatus, Objectives},
+    spawner::{CallWaveEarlyEvent, SpawnerStates, SpawningPaused},
     tilemap::{AtlasHandle, SCALE, TILE_SIZE},
     tool_selector::SelectedTool,
-    ui::{self, slice_image_mode, UiAssets, TITLE_TEXT},
-    waves::Waves,
-    worker::{Idle, Worker},
+    ui::{self, slice_image_mode, UiAssets, BUTTON_TEXT, TITLE_TEXT},
+    waves::{self, Waves},
+    worker::{Idle, Worker, WorkerDiedEvent, REINFORCEMENT_SECS},
     GameState,
 };
 
+pub const OBJECTIVE_COMPLETE: Color = Color::srgb(0.5, 0.9, 0.5);
+pub const OBJECTIVE_FAILED: Color = Color::srgb(0.9, 0.4, 0.4);
+
 pub struct HudPlugin;
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
@@ -34,9 +40,22 @@ impl Plugin for HudPlugin {
                     update_stone,
                     update_metal,
                     update_crystal,
-                    update_wave_count,
+                    update_wave_count.after(waves::reload),
                 ),
             )
+            .add_systems(
+                Update,
+                (
+                    reload_toast,
+                    workers_lost_toast,
+                    update_toasts,
+                    update_objectives,
+                    update_call_wave_button,
+                    call_wave_button,
+                    call_wave_keyboard,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
             .add_systems(OnExit(GameState::GameOver), cleanup);
     }
 }
@@ -71,6 +90,16 @@ pub struct HomeHitPoints;
 #[derive(Component, Default)]
 pub struct WaveCount;
 
+#[derive(Component)]
+pub struct ObjectivesContainer;
+
+#[derive(Component)]
+pub struct CallWaveButton;
+
+/// A short message shown at the top of the screen that disappears after a while.
+#[derive(Component)]
+pub struct Toast(Timer);
+
 #[derive(Resource)]
 pub struct EntityCountUpdateTimer(Timer);
 impl Default for EntityCountUpdateTimer {
@@ -196,8 +225,53 @@ fn init(mut commands: Commands, assets: Res<UiAssets>, atlas_handle: Res<AtlasHa
                 ))
                 .with_children(|parent| {
                     parent.spawn((hud_item("0/0", &atlas_handle, 103 * 48 + 94), WaveCount));
+                    parent.spawn((
+                        Button,
+                        Node {
+                            display: Display::None,
+                            width: Val::Percent(100.),
+                            height: Val::Px(30.),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ImageNode {
+                            image: assets.nine_button.clone(),
+                            image_mode: slice_image_mode(),
+                            ..default()
+                        },
+                        CallWaveButton,
+                        children![(
+                            Text::new("Call wave"),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(BUTTON_TEXT),
+                        )],
+                    ));
                 });
         });
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            top: Val::Px(5.),
+            right: Val::Px(5.),
+            padding: UiRect::all(Val::Px(12.)),
+            row_gap: Val::Px(5.),
+            ..default()
+        },
+        ImageNode {
+            image: assets.nine_panel.clone(),
+            image_mode: slice_image_mode(),
+            ..default()
+        },
+        ObjectivesContainer,
+        HudRoot,
+        Name::new("Objectives"),
+    ));
 }
 
 fn hud_item(
@@ -282,10 +356,12 @@ fn update_idle_workers(
     hasnt_idle: Query<(), (With<Worker>, Without<Idle>)>,
     added_idle: Query<(), Added<Idle>>,
     removed_idle: RemovedComponents<Idle>,
+    removed_workers: RemovedComponents<Worker>,
     item_query: Query<&Children, With<IdleWorkers>>,
     mut text_query: Query<(&mut Text, &mut TextColor)>,
 ) {
-    if added_idle.is_empty() && removed_idle.is_empty() {
+    // Busy workers that are killed don't remove `Idle`.
+    if added_idle.is_empty() && removed_idle.is_empty() && removed_workers.is_empty() {
         return;
     }
 
@@ -473,6 +549,7 @@ fn update_crystal(
 
 fn update_wave_count(
     waves: Option<Res<Waves>>,
+    mut reload_events: EventReader<LevelReloadedEvent>,
     item_query: Query<&Children, With<WaveCount>>,
     mut text_query: Query<&mut Text>,
 ) {
@@ -480,7 +557,10 @@ fn update_wave_count(
         return;
     };
 
-    if !waves.is_changed() {
+    // Waves may be replaced without changing `Waves` when a level is reloaded.
+    let reloaded = !reload_events.is_empty();
+    reload_events.clear();
+    if !waves.is_changed() && !reloaded {
         return;
     }
 
@@ -495,13 +575,186 @@ fn update_wave_count(
 
     // TODO color
 
+    let current = waves.current + 1;
+
+    // Endless mode keeps going past the level's waves.
+    if waves.endless.is_some() {
+        text.0 = format!("{current}");
+        return;
+    }
+
     let num = waves.waves.len();
-    let current = (waves.current + 1).min(num);
+    text.0 = format!("{}/{}", current.min(num), num);
+}
+
+fn update_objectives(
+    mut commands: Commands,
+    objectives: Res<Objectives>,
+    container_query: Query<Entity, With<ObjectivesContainer>>,
+) {
+    if !objectives.is_changed() {
+        return;
+    }
+
+    let Ok(container) = container_query.single() else {
+        return;
+    };
+
+    commands.entity(container).despawn_related::<Children>();
+
+    let line = |objective: &ObjectiveProgress, bonus: bool| {
+        let mut text = objective.objective.description();
+        if !objective.progress.is_empty() {
+            text += &format!(" ({})", objective.progress);
+        }
+        if bonus {
+            text += &format!(" +{}", objective.points);
+        }
+
+        let color = match objective.status {
+            ObjectiveStatus::InProgress => TITLE_TEXT,
+            ObjectiveStatus::Complete => OBJECTIVE_COMPLETE,
+            ObjectiveStatus::Failed => OBJECTIVE_FAILED,
+        };
+
+        (
+            Text::new(text),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(color),
+        )
+    };
+
+    commands.entity(container).with_children(|parent| {
+        for objective in &objectives.required {
+            parent.spawn(line(objective, false));
+        }
+
+        if objectives.bonus.is_empty() {
+            return;
+        }
+
+        parent.spawn((
+            Text::new("Bonus"),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(TITLE_TEXT),
+        ));
+        for objective in &objectives.bonus {
+            parent.spawn(line(objective, true));
+        }
+    });
+}
+
+/// Only shows the call wave button while there's a wave to call early.
+fn update_call_wave_button(
+    states: Res<SpawnerStates>,
+    paused: Res<SpawningPaused>,
+    mut button_query: Query<&mut Node, With<CallWaveButton>>,
+) {
+    let display = if !paused.0 && states.time_to_wave().is_some() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    for mut node in &mut button_query {
+        if node.display != display {
+            node.display = display;
+        }
+    }
+}
+
+fn call_wave_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<CallWaveButton>)>,
+    mut events: EventWriter<CallWaveEarlyEvent>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            events.write(CallWaveEarlyEvent);
+        }
+    }
+}
+
+fn call_wave_keyboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut events: EventWriter<CallWaveEarlyEvent>,
+) {
+    if keys.just_pressed(KeyCode::KeyN) {
+        events.write(CallWaveEarlyEvent);
+    }
+}
+
+fn toast(text: impl Into<String>, duration: f32, assets: &UiAssets) -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.),
+            justify_self: JustifySelf::Center,
+            padding: UiRect::all(Val::Px(12.)),
+            ..default()
+        },
+        ImageNode {
+            image: assets.nine_panel_info.clone(),
+            image_mode: slice_image_mode(),
+            ..default()
+        },
+        Toast(Timer::from_seconds(duration, TimerMode::Once)),
+        Name::new("Toast"),
+        children![(
+            Text::new(text),
+            TextFont {
+                font_size: 15.0,
+                ..default()
+            },
+            TextColor(TITLE_TEXT),
+        )],
+    )
+}
+
+fn reload_toast(
+    mut commands: Commands,
+    mut events: EventReader<LevelReloadedEvent>,
+    assets: Res<UiAssets>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    commands.spawn(toast("Level reloaded", 3., &assets));
+}
+
+fn workers_lost_toast(
+    mut commands: Commands,
+    mut events: EventReader<WorkerDiedEvent>,
+    assets: Res<UiAssets>,
+) {
+    if !events.read().any(|event| event.remaining == 0) {
+        return;
+    }
 
-    text.0 = format!("{}/{}", current, num);
+    commands.spawn(toast(
+        format!("Every worker was lost! A new one will arrive in {REINFORCEMENT_SECS:.0}s."),
+        5.,
+        &assets,
+    ));
+}
+
+fn update_toasts(mut commands: Commands, mut query: Query<(Entity, &mut Toast)>, time: Res<Time>) {
+    for (entity, mut toast) in &mut query {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
 }
 
-fn cleanup(mut commands: Commands, query: Query<Entity, With<HudRoot>>) {
+fn cleanup(mut commands: Commands, query: Query<Entity, Or<(With<HudRoot>, With<Toast>)>>) {
     for entity in &query {
         commands.entity(entity).despawn();
     }
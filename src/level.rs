@@ -1,60 +1,211 @@
 use bevy::prelude::*;
 use bevy_common_assets::ron::RonAssetPlugin;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     critter::CritterKind,
     currency::Currency,
     loading::{LoadingAssets, LoadingResources},
-    tilemap::TilePos,
+    objective::{BonusObjective, Objective},
+    palette::TileProperties,
+    score::Scoring,
+    tilemap::{Map, TileKind, TilePos},
     waves::Wave,
     GameState,
 };
 
+/// The catalog of levels that the player can choose from.
+pub const LEVEL_CATALOG: &str = "levels/levels.catalog.ron";
+
 pub struct LevelPlugin;
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(RonAssetPlugin::<LevelConfig>::new(&["level.ron"]))
+            .add_plugins(RonAssetPlugin::<LevelCatalog>::new(&["catalog.ron"]))
+            .init_resource::<LevelHandles>()
+            .add_event::<LevelReloadedEvent>()
             .add_systems(OnEnter(GameState::Loading), queue_load)
-            .add_systems(Update, check_load.run_if(in_state(GameState::Loading)));
+            .add_systems(Update, check_load.run_if(in_state(GameState::Loading)))
+            .add_systems(Update, detect_reload.run_if(in_state(GameState::Playing)));
     }
 }
 
-#[derive(Asset, TypePath, Deserialize)]
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone)]
 pub struct LevelConfig {
     pub map: String,
+    /// Refuse to load the map if any of its pixels have colors that don't map to a
+    /// tile, instead of just logging a warning.
+    #[serde(default)]
+    pub strict_map: bool,
+    /// The palette used to turn the map's pixel colors into tiles. Defaults to
+    /// [`DEFAULT_PALETTE`].
+    ///
+    /// [`DEFAULT_PALETTE`]: crate::palette::DEFAULT_PALETTE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette: Option<String>,
     pub workers: usize,
+    /// Where workers are spawned. Defaults to spreading them across the map's home
+    /// tiles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_spawn: Option<TilePos>,
     pub currency: Currency,
     pub waves: Vec<Wave>,
     pub critters: Vec<(TilePos, CritterKind)>,
+    #[serde(default)]
+    pub tuning: Tuning,
+    /// What the player has to do to win. Defaults to surviving every wave.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objectives: Vec<Objective>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bonus_objectives: Vec<BonusObjective>,
+    #[serde(default)]
+    pub scoring: Scoring,
+}
+
+/// Per-level overrides for how long things take to dig and build.
+///
+/// Tile hit points that are left out come from the map's [`Palette`], or the
+/// tile kind's built-in value if the palette doesn't set one either.
+///
+/// [`Palette`]: crate::palette::Palette
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Tuning {
+    /// Defaults to 30.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub home_hit_points: Option<u32>,
+    /// Defaults to 8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stone_hit_points: Option<u32>,
+    /// Hit points of hidden crystal and metal. Defaults to 40.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ore_hit_points: Option<u32>,
+    /// The amount of work it takes to build a tower. Defaults to
+    /// [`DEFAULT_TOWER_HIT_POINTS`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tower_hit_points: Option<u32>,
+}
+impl Tuning {
+    /// Overrides the hit points in a map's tile properties.
+    pub fn apply(&self, properties: &mut TileProperties) {
+        let overrides = [
+            (TileKind::Home, self.home_hit_points),
+            (TileKind::HomeTwo, self.home_hit_points),
+            (TileKind::Stone, self.stone_hit_points),
+            (TileKind::CrystalHidden, self.ore_hit_points),
+            (TileKind::MetalHidden, self.ore_hit_points),
+        ];
+
+        for (kind, hit_points) in overrides {
+            if hit_points.is_some() {
+                properties.0.entry(kind).or_default().hit_points = hit_points;
+            }
+        }
+    }
+
+    pub fn tower_hit_points(&self) -> u32 {
+        self.tower_hit_points.unwrap_or(DEFAULT_TOWER_HIT_POINTS)
+    }
 }
 
+pub const DEFAULT_TOWER_HIT_POINTS: u32 = 10;
+
+/// The list of levels that the player can choose from.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct LevelCatalog {
+    pub levels: Vec<LevelInfo>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LevelInfo {
+    pub name: String,
+    pub description: String,
+    pub path: String,
+}
+
+#[derive(Resource)]
+pub struct LevelCatalogHandle(pub Handle<LevelCatalog>);
+
+/// The level that is currently being played.
 #[derive(Resource)]
 pub struct LevelHandle(pub Handle<LevelConfig>);
 
+/// Sent when the level that is being played is modified, e.g. when its file is
+/// changed while the `hot_reload` feature is enabled.
+#[derive(Event)]
+pub struct LevelReloadedEvent;
+
+/// Handles for every level in the [`LevelCatalog`], in catalog order.
+///
+/// These are kept around so that every level and its map are already loaded
+/// by the time the player picks one.
+#[derive(Resource, Default)]
+pub struct LevelHandles {
+    pub levels: Vec<Handle<LevelConfig>>,
+    pub maps: Vec<Handle<Map>>,
+}
+
 fn queue_load(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut loading_assets: ResMut<LoadingAssets>,
     mut loading_resources: ResMut<LoadingResources>,
 ) {
-    let handle = asset_server.load("levels/1.level.ron");
+    let handle = asset_server.load(LEVEL_CATALOG);
     loading_assets.0.push(handle.id().into());
-    commands.insert_resource(LevelHandle(handle));
+    commands.insert_resource(LevelCatalogHandle(handle));
     loading_resources.0 += 1;
 }
 
 fn check_load(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
     mut loading_resources: ResMut<LoadingResources>,
-    level_handle: Option<Res<LevelHandle>>,
+    catalog_handle: Option<Res<LevelCatalogHandle>>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    mut level_handles: ResMut<LevelHandles>,
     mut done: Local<bool>,
 ) {
     if *done {
         return;
     }
 
-    if level_handle.is_some() {
-        loading_resources.0 -= 1;
+    let Some(catalog_handle) = catalog_handle else {
+        return;
+    };
+
+    let Some(catalog) = catalogs.get(&catalog_handle.0) else {
+        return;
+    };
+
+    for info in &catalog.levels {
+        let handle = asset_server.load(&info.path);
+        loading_assets.0.push(handle.id().into());
+        level_handles.levels.push(handle);
+    }
+
+    let Some(first) = level_handles.levels.first() else {
+        error!("Level catalog contains no levels.");
         *done = true;
+        return;
+    };
+
+    commands.insert_resource(LevelHandle(first.clone()));
+
+    loading_resources.0 -= 1;
+    *done = true;
+}
+
+fn detect_reload(
+    mut events: EventReader<AssetEvent<LevelConfig>>,
+    mut writer: EventWriter<LevelReloadedEvent>,
+    level_handle: Res<LevelHandle>,
+) {
+    for event in events.read() {
+        if event.is_modified(&level_handle.0) {
+            info!("Level reloaded.");
+            writer.write(LevelReloadedEvent);
+        }
     }
 }
@@ -0,0 +1,283 @@
+use bevy::prelude::*;
+
+use crate::{
+    endless::GameMode,
+    level::{LevelCatalog, LevelCatalogHandle, LevelHandle, LevelHandles},
+    main_menu::MenuState,
+    settings::{DifficultySetting, ProgressSetting},
+    tilemap::TilemapHandle,
+    ui::{slice_image_mode, UiAssets, BUTTON_TEXT, TITLE_TEXT},
+    GameState,
+};
+
+pub struct LevelSelectPlugin;
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuState::LevelSelect), setup_menu)
+            .add_systems(
+                Update,
+                (level_button, edit_button, back_button).run_if(in_state(MenuState::LevelSelect)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct LevelButton(usize, GameMode);
+#[derive(Component)]
+struct EditButton(usize);
+#[derive(Component)]
+struct BackButton;
+
+fn setup_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    catalog_handle: Res<LevelCatalogHandle>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    progress: Res<ProgressSetting>,
+    difficulty: Res<DifficultySetting>,
+) {
+    let button_node = (
+        Node {
+            width: Val::Px(250.0),
+            height: Val::Px(45.0),
+            margin: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ImageNode {
+            image: ui_assets.nine_button.clone(),
+            image_mode: slice_image_mode(),
+            ..default()
+        },
+    );
+    let button_text_style = (
+        TextFont {
+            font_size: 15.0,
+            ..default()
+        },
+        TextColor(BUTTON_TEXT),
+    );
+    let title_text_style = (
+        TextFont {
+            font_size: 15.0,
+            ..default()
+        },
+        TextColor(TITLE_TEXT),
+    );
+    let description_text_style = (
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(TITLE_TEXT),
+    );
+
+    let container = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Auto),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(20.)),
+                max_width: Val::Px(500.),
+                ..default()
+            },
+            ImageNode {
+                image: ui_assets.nine_panel.clone(),
+                image_mode: slice_image_mode(),
+                ..default()
+            },
+            StateScoped(MenuState::LevelSelect),
+        ))
+        .id();
+
+    let title = commands
+        .spawn((
+            Text::new("Select Level"),
+            title_text_style,
+            Node {
+                margin: UiRect {
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .id();
+
+    commands.entity(container).add_child(title);
+
+    let Some(catalog) = catalogs.get(&catalog_handle.0) else {
+        warn!("Couldn't find level catalog when building level select menu.");
+        return;
+    };
+
+    for (i, info) in catalog.levels.iter().enumerate() {
+        let row = commands.spawn(Node::default()).id();
+
+        let unlocked = progress.unlocked(catalog, i);
+
+        let button = if unlocked {
+            commands
+                .spawn((
+                    Button,
+                    button_node.clone(),
+                    LevelButton(i, GameMode::Campaign),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((Text::new(info.name.clone()), button_text_style.clone()));
+                })
+                .id()
+        } else {
+            let (node, mut image) = button_node.clone();
+            image.image = ui_assets.nine_panel.clone();
+
+            commands
+                .spawn((node, image))
+                .with_children(|parent| {
+                    parent.spawn((Text::new(info.name.clone()), description_text_style.clone()));
+                })
+                .id()
+        };
+
+        commands.entity(row).add_child(button);
+
+        // Endless mode is unlocked by completing the level.
+        if progress.completed(&info.path) {
+            let (mut node, image) = button_node.clone();
+            node.width = Val::Px(90.0);
+
+            let endless_button = commands
+                .spawn((Button, node, image, LevelButton(i, GameMode::Endless)))
+                .with_children(|parent| {
+                    parent.spawn((Text::new("Endless"), button_text_style.clone()));
+                })
+                .id();
+
+            commands.entity(row).add_child(endless_button);
+        }
+
+        // The editor saves levels straight to the assets directory, which isn't
+        // possible on the web.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (mut node, image) = button_node.clone();
+            node.width = Val::Px(60.0);
+
+            let edit_button = commands
+                .spawn((Button, node, image, EditButton(i)))
+                .with_children(|parent| {
+                    parent.spawn((Text::new("Edit"), button_text_style.clone()));
+                })
+                .id();
+
+            commands.entity(row).add_child(edit_button);
+        }
+
+        let mut description = info.description.clone();
+        if !unlocked {
+            description = format!("Complete {} to unlock.", catalog.levels[i - 1].name);
+        } else if let Some(best) = progress.best(&info.path, &difficulty) {
+            description += &format!("\nBest on {}: {}", *difficulty, best.score);
+            if best.stars > 0 {
+                description += &format!(" ({}/3 stars)", best.stars);
+            }
+        } else if progress.completed(&info.path) {
+            description += "\nCompleted";
+        }
+        if let Some(wave) = progress.best_wave(&info.path, &difficulty) {
+            description += &format!("\nBest endless wave on {}: {}", *difficulty, wave);
+        }
+
+        let description = commands
+            .spawn((
+                Text::new(description),
+                description_text_style.clone(),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    margin: UiRect {
+                        bottom: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+
+        commands.entity(container).add_children(&[row, description]);
+    }
+
+    let back_button = commands
+        .spawn((Button, button_node, BackButton))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Back"), button_text_style));
+        })
+        .id();
+
+    commands.entity(container).add_child(back_button);
+}
+
+fn level_button(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    level_handles: Res<LevelHandles>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (Some(level), Some(map)) = (
+            level_handles.levels.get(button.0),
+            level_handles.maps.get(button.0),
+        ) else {
+            warn!("Selected level {} does not exist.", button.0);
+            continue;
+        };
+
+        commands.insert_resource(LevelHandle(level.clone()));
+        commands.insert_resource(TilemapHandle(map.clone()));
+        commands.insert_resource(button.1);
+
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn edit_button(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &EditButton), Changed<Interaction>>,
+    level_handles: Res<LevelHandles>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (Some(level), Some(map)) = (
+            level_handles.levels.get(button.0),
+            level_handles.maps.get(button.0),
+        ) else {
+            warn!("Selected level {} does not exist.", button.0);
+            continue;
+        };
+
+        commands.insert_resource(LevelHandle(level.clone()));
+        commands.insert_resource(TilemapHandle(map.clone()));
+
+        next_state.set(GameState::Editor);
+    }
+}
+
+fn back_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut next_state: ResMut<NextState<MenuState>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            next_state.set(MenuState::Main);
+        }
+    }
+}
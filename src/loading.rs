@@ -4,6 +4,7 @@ use strum::IntoEnumIterator;
 
 use crate::{
     enemy::EnemyKind,
+    enemy_table::Enemies,
     tilemap::{AtlasHandle, TileKind},
     ui::{slice_image_mode, UiAssets},
     GameState,
@@ -73,6 +74,7 @@ fn init_loading_scene(
     mut commands: Commands,
     maybe_atlas_handle: Option<Res<AtlasHandle>>,
     ui_assets: Res<UiAssets>,
+    enemies: Enemies,
     mut done: Local<bool>,
 ) {
     if *done {
@@ -83,6 +85,10 @@ fn init_loading_scene(
         return;
     };
 
+    let Some(enemy_table) = enemies.table() else {
+        return;
+    };
+
     commands
         .spawn((
             Node {
@@ -107,7 +113,7 @@ fn init_loading_scene(
                     image: atlas_handle.image.clone(),
                     texture_atlas: Some(TextureAtlas {
                         layout: atlas_handle.layout.clone(),
-                        index: EnemyKind::Ent.atlas_index(),
+                        index: enemy_table.stats(EnemyKind::Ent).sprite,
                     }),
                     ..default()
                 },
@@ -7,20 +7,27 @@ use camera::CameraPlugin;
 use critter::CritterPlugin;
 use currency::CurrencyPlugin;
 use cursor::CursorPlugin;
+use damage::DamagePlugin;
 use designate_tool::DesignateToolPlugin;
+use editor::EditorPlugin;
+use endless::EndlessPlugin;
 use enemy::EnemyPlugin;
+use enemy_table::EnemyTablePlugin;
 use game::GamePlugin;
 use game_over::GameOverPlugin;
 use home::HomePlugin;
 use hud::HudPlugin;
 use level::LevelPlugin;
+use level_select::LevelSelectPlugin;
 use loading::LoadingPlugin;
 use main_menu::MainMenuPlugin;
 use map_loader::MapFileLoaderPlugin;
 use movement::MovementPlugin;
+use objective::ObjectivePlugin;
 use particle::ParticlePlugin;
 use pathfinding::PathfindingPlugin;
 use radio_button::RadioButtonPlugin;
+use score::ScorePlugin;
 use settings::SettingsPlugin;
 use sound::MusicPlugin;
 use spawner::SpawnerPlugin;
@@ -31,6 +38,7 @@ use tool_selector::ToolSelectorPlugin;
 use tower::TowerPlugin;
 use tutorial::TutorialPlugin;
 use ui::UiPlugin;
+use wave_preview::WavePreviewPlugin;
 use waves::WavesPlugin;
 use worker::WorkerPlugin;
 
@@ -44,8 +52,12 @@ mod camera;
 mod critter;
 mod currency;
 mod cursor;
+mod damage;
 mod designate_tool;
+mod editor;
+mod endless;
 mod enemy;
+mod enemy_table;
 mod game;
 mod game_over;
 mod hit_points;
@@ -53,24 +65,34 @@ mod home;
 mod hud;
 mod layer;
 mod level;
+mod level_select;
 mod loading;
 mod main_menu;
 mod map_loader;
+mod map_text;
+mod mapgen;
 mod movement;
+mod objective;
+mod palette;
 mod particle;
 mod pathfinding;
 mod radio_button;
+mod score;
 mod settings;
 mod sound;
 mod spawner;
 mod stats;
 mod stone;
+mod tiled;
 mod tilemap;
 mod tool_selector;
+mod tools;
 mod tower;
 mod tutorial;
 mod ui;
 mod util;
+mod validation;
+mod wave_preview;
 mod waves;
 mod worker;
 
@@ -81,9 +103,15 @@ enum GameState {
     MainMenu,
     Playing,
     GameOver,
+    Editor,
 }
 
 fn main() {
+    // Level authoring tools, e.g. `entytd convert-map map.map.png map.map.txt`
+    if let Some(code) = tools::run(std::env::args().skip(1)) {
+        std::process::exit(code);
+    }
+
     let mut app = App::new();
 
     app.add_plugins((
@@ -133,6 +161,12 @@ fn main() {
         GameOverPlugin,
         GamePlugin,
         StatsPlugin,
+        ObjectivePlugin,
+        ScorePlugin,
+        EndlessPlugin,
+        WavePreviewPlugin,
+        EnemyTablePlugin,
+        DamagePlugin,
     ));
 
     app.add_plugins((
@@ -142,8 +176,10 @@ fn main() {
         CursorPlugin,
         HudPlugin,
         MainMenuPlugin,
+        LevelSelectPlugin,
         UiPlugin,
         TutorialPlugin,
+        EditorPlugin,
     ));
 
     #[cfg(feature = "inspector")]
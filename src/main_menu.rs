@@ -17,7 +17,10 @@ pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<MainMenuAssets>()
-            .add_systems(OnEnter(GameState::MainMenu), (setup_menu, init_background))
+            .add_sub_state::<MenuState>()
+            .enable_state_scoped_entities::<MenuState>()
+            .add_systems(OnEnter(GameState::MainMenu), init_background)
+            .add_systems(OnEnter(MenuState::Main), setup_menu)
             .add_systems(
                 Update,
                 (
@@ -35,6 +38,15 @@ impl Plugin for MainMenuPlugin {
     }
 }
 
+/// The screen currently shown while in [`GameState::MainMenu`].
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(GameState = GameState::MainMenu)]
+pub(crate) enum MenuState {
+    #[default]
+    Main,
+    LevelSelect,
+}
+
 #[derive(Resource)]
 pub struct MainMenuAssets {
     pub map: Handle<Map>,
@@ -133,7 +145,7 @@ fn setup_menu(
                 image_mode: slice_image_mode(),
                 ..default()
             },
-            StateScoped(GameState::MainMenu),
+            StateScoped(MenuState::Main),
         ))
         .id();
 
@@ -250,11 +262,11 @@ fn setup_menu(
 
 fn play_button(
     mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<PlayButton>)>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut next_state: ResMut<NextState<MenuState>>,
 ) {
     for interaction in interaction_query.iter_mut() {
         if *interaction == Interaction::Pressed {
-            next_state.set(GameState::Playing);
+            next_state.set(MenuState::LevelSelect);
         }
     }
 }
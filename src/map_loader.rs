@@ -1,21 +1,65 @@
-use crate::tilemap::Map;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use crate::{
+    map_text::{map_from_text, MapTextError, UnmappedChar},
+    palette::{Palette, DEFAULT_PALETTE},
+    tiled::{map_from_tiled, TiledError, TiledMap, TiledTileset, UnmappedTile},
+    tilemap::{Map, TileKind},
+};
 use bevy::{
-    asset::{io::Reader, AssetLoader, LoadContext},
+    asset::{io::Reader, AssetLoader, LoadContext, ReadAssetBytesError},
     prelude::*,
 };
-use image::{GenericImageView, ImageError, Pixel};
+use image::{DynamicImage, GenericImageView, ImageError, Pixel, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub struct MapFileLoaderPlugin;
 impl Plugin for MapFileLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.init_asset_loader::<MapFileLoader>();
+        app.init_asset_loader::<MapFileLoader>()
+            .init_asset_loader::<MapTextFileLoader>()
+            .init_asset_loader::<TiledMapLoader>();
     }
 }
 
 #[derive(Default)]
 pub struct MapFileLoader;
 
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct MapFileLoaderSettings {
+    /// When `true`, fail to load maps containing tiles that can't be mapped to a
+    /// tile kind, such as pixels whose colors aren't in the [`Palette`]. Otherwise,
+    /// those tiles are left empty and a warning is logged.
+    pub strict: bool,
+    /// Path to the [`Palette`] used to map pixel colors to tiles. Defaults to
+    /// [`DEFAULT_PALETTE`].
+    pub palette: Option<String>,
+}
+
+/// A pixel whose color isn't in the map's [`Palette`].
+///
+/// `x` and `y` are image coordinates, with the origin at the top left, as they
+/// would appear in an image editor.
+#[derive(Debug, Clone, Copy)]
+pub struct UnmappedPixel {
+    pub x: u32,
+    pub y: u32,
+    pub rgb: [u8; 3],
+}
+impl Display for UnmappedPixel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({}, {}) rgb({}, {}, {})",
+            self.x, self.y, self.rgb[0], self.rgb[1], self.rgb[2]
+        )
+    }
+}
+
 /// Possible errors that can be produced by [`MapFileLoader`]
 #[non_exhaustive]
 #[derive(Debug, Error)]
@@ -24,17 +68,137 @@ pub enum MapFileLoaderError {
     Io(#[from] std::io::Error),
     #[error("Could not load image file: {0}")]
     Image(#[from] ImageError),
+    #[error("Could not read palette: {0}")]
+    ReadPalette(#[from] ReadAssetBytesError),
+    #[error("Could not parse palette: {0}")]
+    Palette(#[from] ron::error::SpannedError),
+    #[error("Map contains {} pixel(s) with unmapped colors: {}", .0.len(), join(.0))]
+    UnmappedPixels(Vec<UnmappedPixel>),
+    #[error("Text map is not valid UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error("Could not parse text map: {0}")]
+    Text(#[from] MapTextError),
+    #[error("Map contains {} character(s) missing from the legend: {}", .0.len(), join(.0))]
+    UnmappedChars(Vec<UnmappedChar>),
+    #[error("Could not read tileset: {0}")]
+    ReadTileset(ReadAssetBytesError),
+    #[error("Could not parse Tiled map: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Could not convert Tiled map: {0}")]
+    Tiled(#[from] TiledError),
+    #[error("Map contains {} tile(s) without a tile kind: {}", .0.len(), join(.0))]
+    UnmappedTiles(Vec<UnmappedTile>),
+}
+
+fn join<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A tile kind that has no color in the [`Palette`] and can't be written to a map
+/// image.
+#[derive(Debug, Error)]
+#[error("Palette has no color for {0:?}")]
+pub struct MissingColorError(pub TileKind);
+
+async fn load_palette(
+    settings: &MapFileLoaderSettings,
+    load_context: &mut LoadContext<'_>,
+) -> Result<Palette, MapFileLoaderError> {
+    let palette_path = settings.palette.as_deref().unwrap_or(DEFAULT_PALETTE);
+    let palette_bytes = load_context.read_asset_bytes(palette_path).await?;
+    Ok(ron::de::from_bytes(&palette_bytes)?)
+}
+
+/// Builds a [`Map`] from a map image, along with any pixels whose colors aren't in
+/// the [`Palette`]. Those pixels are left empty.
+pub fn map_from_image(img: &DynamicImage, palette: &Palette) -> (Map, Vec<UnmappedPixel>) {
+    let mut map = Map::new(img.height() as usize, img.width() as usize);
+    map.1 = palette.tiles.clone();
+
+    let mut unmapped = vec![];
+
+    for (x, y, rgba) in img.pixels() {
+        let rgb = rgba.to_rgb().0;
+        let Some(kind) = palette.tile_kind(rgb) else {
+            unmapped.push(UnmappedPixel { x, y, rgb });
+            continue;
+        };
+
+        let inv_y = img.height() - y - 1;
+
+        map.0[(inv_y as usize, x as usize)] = kind;
+    }
+
+    (map, unmapped)
+}
+
+/// Builds a map image from a [`Map`], using the first color (in RGB order) that the
+/// [`Palette`] assigns to each tile kind.
+pub fn map_to_image(map: &Map, palette: &Palette) -> Result<RgbImage, MissingColorError> {
+    let mut colors = HashMap::<TileKind, [u8; 3]>::new();
+    for (rgb, kind) in &palette.colors {
+        colors
+            .entry(*kind)
+            .and_modify(|existing| *existing = (*existing).min(*rgb))
+            .or_insert(*rgb);
+    }
+
+    let (width, height) = (map.0.cols(), map.0.rows());
+    let mut img = RgbImage::new(width as u32, height as u32);
+
+    for x in 0..width {
+        for y in 0..height {
+            let kind = map.0[(y, x)];
+            let Some(rgb) = colors.get(&kind) else {
+                return Err(MissingColorError(kind));
+            };
+
+            let inv_y = height - y - 1;
+
+            img.put_pixel(x as u32, inv_y as u32, Rgb(*rgb));
+        }
+    }
+
+    Ok(img)
+}
+
+/// Logs a summary of any problems found while loading a map in lenient mode.
+fn warn_unmapped<T: Display, K: Ord>(
+    load_context: &LoadContext<'_>,
+    unmapped: &[T],
+    key: impl Fn(&T) -> K,
+    what: &str,
+) {
+    // Summarize by color or character, because a single mistake is usually
+    // repeated many times.
+    let mut grouped = BTreeMap::<K, (usize, &T)>::new();
+    for item in unmapped {
+        grouped.entry(key(item)).or_insert((0, item)).0 += 1;
+    }
+    for (count, first) in grouped.values() {
+        warn!(
+            "{}: {} tile(s) with unmapped {}, first at {}",
+            load_context.path().display(),
+            count,
+            what,
+            first
+        );
+    }
 }
 
 impl AssetLoader for MapFileLoader {
     type Asset = Map;
-    type Settings = ();
+    type Settings = MapFileLoaderSettings;
     type Error = MapFileLoaderError;
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        settings: &MapFileLoaderSettings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
@@ -44,22 +208,162 @@ impl AssetLoader for MapFileLoader {
         reader.no_limits();
         let dyn_img = reader.decode()?;
 
-        let mut map = Map::new(dyn_img.height() as usize, dyn_img.width() as usize);
+        let palette = load_palette(settings, load_context).await?;
+
+        let (map, unmapped) = map_from_image(&dyn_img, &palette);
+
+        if unmapped.is_empty() {
+            return Ok(map);
+        }
+
+        if settings.strict {
+            return Err(MapFileLoaderError::UnmappedPixels(unmapped));
+        }
+
+        warn_unmapped(load_context, &unmapped, |p| p.rgb, "color");
+
+        Ok(map)
+    }
 
-        for (x, y, rgba) in dyn_img.pixels() {
-            let Ok(kind) = rgba.to_rgb().0.try_into() else {
+    fn extensions(&self) -> &[&str] {
+        &["map.png"]
+    }
+}
+
+/// Loads maps from `.map.txt` files, a plain-text format that is easier to edit
+/// and review than a map image.
+///
+/// The file begins with a legend that assigns a character to each tile kind, one
+/// per line in the form `<character> = <TileKind>`. The legend is followed by a
+/// `---` separator and then the map itself, one character per tile, with the top
+/// row first.
+///
+/// ```text
+/// . = Dirt
+/// # = Stone
+/// H = Home
+/// ---
+/// #####
+/// #.H.#
+/// #####
+/// ```
+///
+/// The map's [`Palette`] is only used for its tile properties.
+#[derive(Default)]
+pub struct MapTextFileLoader;
+
+impl AssetLoader for MapTextFileLoader {
+    type Asset = Map;
+    type Settings = MapFileLoaderSettings;
+    type Error = MapFileLoaderError;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &MapFileLoaderSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes)?;
+
+        let palette = load_palette(settings, load_context).await?;
+
+        let (mut map, unmapped) = map_from_text(&text)?;
+        map.1 = palette.tiles;
+
+        if unmapped.is_empty() {
+            return Ok(map);
+        }
+
+        if settings.strict {
+            return Err(MapFileLoaderError::UnmappedChars(unmapped));
+        }
+
+        warn_unmapped(load_context, &unmapped, |c| c.character, "character");
+
+        Ok(map)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.txt"]
+    }
+}
+
+/// Loads maps exported from the [Tiled](https://www.mapeditor.org/) editor in its
+/// JSON format (`.tmj`). TMX files must be re-exported as JSON first.
+///
+/// Tiles get their tile kind from a string property named `kind` on the tile in
+/// its tileset, e.g. `Stone`. Tilesets may be embedded or stored in external JSON
+/// (`.tsj`) files. Later layers are drawn over earlier ones.
+///
+/// Objects in object layers are placed on the tile under their center, and are
+/// identified by their class:
+///
+/// - `spawner` places a spawner tile. An optional integer `index` property sets
+///   its [`SpawnerIndex`].
+/// - `home` places a home tile. An optional `kind` property chooses another
+///   tile kind, e.g. `HomeTwo`.
+/// - `critter` places a critter whose kind is given by a `kind` property, e.g.
+///   `Llama`.
+///
+/// Objects without a class are ignored. The map's [`Palette`] is only used for its
+/// tile properties.
+///
+/// [`SpawnerIndex`]: crate::spawner::SpawnerIndex
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = Map;
+    type Settings = MapFileLoaderSettings;
+    type Error = MapFileLoaderError;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &MapFileLoaderSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut tiled: TiledMap = serde_json::from_slice(&bytes)?;
+
+        // External tileset paths are relative to the map.
+        let dir = load_context
+            .path()
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+        for tileset in &mut tiled.tilesets {
+            let Some(source) = &tileset.source else {
                 continue;
             };
 
-            let inv_y = dyn_img.height() - y - 1;
+            let tileset_bytes = load_context
+                .read_asset_bytes(dir.join(source))
+                .await
+                .map_err(MapFileLoaderError::ReadTileset)?;
+            tileset.tileset = serde_json::from_slice::<TiledTileset>(&tileset_bytes)?;
+        }
+
+        let palette = load_palette(settings, load_context).await?;
 
-            map.0[(inv_y as usize, x as usize)] = kind;
+        let (mut map, unmapped) = map_from_tiled(&tiled)?;
+        map.1 = palette.tiles;
+
+        if unmapped.is_empty() {
+            return Ok(map);
+        }
+
+        if settings.strict {
+            return Err(MapFileLoaderError::UnmappedTiles(unmapped));
         }
 
+        warn_unmapped(load_context, &unmapped, |t| t.gid, "tile kind");
+
         Ok(map)
     }
 
     fn extensions(&self) -> &[&str] {
-        &["map.png"]
+        &["tmj"]
     }
 }
@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::tilemap::{Map, TileKind};
+
+const SEPARATOR: &str = "---";
+
+/// Characters that are used for common tile kinds when writing a map. Other tile
+/// kinds are assigned characters from [`FALLBACK_CHARS`].
+const PREFERRED_CHARS: &[(TileKind, char)] = &[
+    (TileKind::Empty, '_'),
+    (TileKind::Dirt, '.'),
+    (TileKind::Stone, '#'),
+    (TileKind::StoneTunnel, '+'),
+    (TileKind::CrystalHidden, 'c'),
+    (TileKind::MetalHidden, 'm'),
+    (TileKind::Mountain, 'M'),
+    (TileKind::Peak, 'A'),
+    (TileKind::Volcano, 'V'),
+    (TileKind::Forest, 'T'),
+    (TileKind::GrassA, ','),
+    (TileKind::GrassB, ';'),
+    (TileKind::River, '~'),
+    (TileKind::Home, 'H'),
+    (TileKind::HomeTwo, 'h'),
+    (TileKind::Bridge, '='),
+    (TileKind::Road, ':'),
+    (TileKind::Spawn, 'S'),
+];
+const FALLBACK_CHARS: &str =
+    "0123456789abdefgijklnopqrstuvwxyzBCDEFGIJKLNOPQRUWXYZ!\"$%&'()*<>?@[]^{|}";
+
+/// Possible errors that can be produced when parsing a text map.
+#[derive(Debug, Error)]
+pub enum MapTextError {
+    #[error("Missing \"{SEPARATOR}\" separator after the legend")]
+    MissingSeparator,
+    #[error("Line {0}: expected \"<character> = <TileKind>\"")]
+    InvalidLegend(usize),
+    #[error("Line {line}: unknown tile kind \"{name}\"")]
+    UnknownTileKind { line: usize, name: String },
+    #[error("Line {line}: '{character}' is already in the legend")]
+    DuplicateLegend { line: usize, character: char },
+    #[error("Map has no rows")]
+    Empty,
+    #[error("Line {line}: expected {expected} tiles, found {found}")]
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// A character in the map that isn't in the legend.
+#[derive(Debug, Clone, Copy)]
+pub struct UnmappedChar {
+    pub line: usize,
+    pub column: usize,
+    pub character: char,
+}
+impl Display for UnmappedChar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {} column {} '{}'",
+            self.line, self.column, self.character
+        )
+    }
+}
+
+/// Builds a [`Map`] from a text map, along with any characters that aren't in the
+/// legend. Those tiles are left empty.
+pub fn map_from_text(text: &str) -> Result<(Map, Vec<UnmappedChar>), MapTextError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let mut legend = HashMap::new();
+    loop {
+        let Some((line, content)) = lines.next() else {
+            return Err(MapTextError::MissingSeparator);
+        };
+
+        if content.trim_end() == SEPARATOR {
+            break;
+        }
+
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let mut chars = content.chars();
+        let Some(character) = chars.next() else {
+            continue;
+        };
+        let Some(name) = chars.as_str().strip_prefix(" = ") else {
+            return Err(MapTextError::InvalidLegend(line));
+        };
+        let name = name.trim();
+        let Ok(kind) = TileKind::from_str(name) else {
+            return Err(MapTextError::UnknownTileKind {
+                line,
+                name: name.to_string(),
+            });
+        };
+
+        if legend.insert(character, kind).is_some() {
+            return Err(MapTextError::DuplicateLegend { line, character });
+        }
+    }
+
+    let mut rows = lines.collect::<Vec<_>>();
+    while rows.last().is_some_and(|(_, row)| row.is_empty()) {
+        rows.pop();
+    }
+
+    let Some((_, first)) = rows.first() else {
+        return Err(MapTextError::Empty);
+    };
+    let width = first.chars().count();
+
+    let mut map = Map::new(rows.len(), width);
+    let mut unmapped = vec![];
+
+    for (y, (line, row)) in rows.iter().enumerate() {
+        let found = row.chars().count();
+        if found != width {
+            return Err(MapTextError::RaggedRow {
+                line: *line,
+                expected: width,
+                found,
+            });
+        }
+
+        let inv_y = rows.len() - y - 1;
+
+        for (x, character) in row.chars().enumerate() {
+            let Some(kind) = legend.get(&character) else {
+                unmapped.push(UnmappedChar {
+                    line: *line,
+                    column: x + 1,
+                    character,
+                });
+                continue;
+            };
+
+            map.0[(inv_y, x)] = *kind;
+        }
+    }
+
+    Ok((map, unmapped))
+}
+
+/// Writes a [`Map`] as a text map, with a legend containing only the tile kinds
+/// that are used.
+pub fn map_to_text(map: &Map) -> String {
+    let used = TileKind::iter()
+        .filter(|kind| map.0.iter().any(|tile| tile == kind))
+        .collect::<Vec<_>>();
+
+    let mut fallback = FALLBACK_CHARS
+        .chars()
+        .filter(|c| !PREFERRED_CHARS.iter().any(|(_, preferred)| preferred == c));
+
+    let mut chars = HashMap::new();
+    let mut text = String::new();
+
+    for kind in used {
+        let character = PREFERRED_CHARS
+            .iter()
+            .find(|(preferred, _)| *preferred == kind)
+            .map(|(_, c)| *c)
+            .or_else(|| fallback.next())
+            .expect("There are more fallback characters than tile kinds.");
+
+        chars.insert(kind, character);
+        text.push_str(&format!("{character} = {kind:?}\n"));
+    }
+
+    text.push_str(SEPARATOR);
+    text.push('\n');
+
+    for y in (0..map.0.rows()).rev() {
+        for x in 0..map.0.cols() {
+            text.push(chars[&map.0[(y, x)]]);
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        // Every tile kind, so that the fallback characters are used too.
+        let kinds = TileKind::iter().collect::<Vec<_>>();
+        let mut map = Map::new(3, kinds.len());
+        for (i, kind) in kinds.iter().enumerate() {
+            map.0[(0, i)] = *kind;
+            map.0[(2, kinds.len() - i - 1)] = *kind;
+        }
+
+        let (parsed, unmapped) = map_from_text(&map_to_text(&map)).unwrap();
+
+        assert!(unmapped.is_empty());
+        assert!(parsed.0 == map.0);
+    }
+
+    #[test]
+    fn unmapped_chars() {
+        let (map, unmapped) = map_from_text("# = Stone\n---\n#?\n##\n").unwrap();
+
+        assert_eq!(unmapped.len(), 1);
+        assert_eq!((unmapped[0].line, unmapped[0].column), (3, 2));
+        assert_eq!(map.0[(1, 1)], TileKind::Empty);
+        assert_eq!(map.0[(0, 1)], TileKind::Stone);
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashSet;
+
+use pathfinding::prelude::{astar, bfs_reach};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::{
+    pathfinding::{enemy_cost_fn, heuristic, EnemyCostClass, NeighborCostIter, NEIGHBORS},
+    tilemap::{Map, TileKind, TilePos},
+};
+
+const MIN_WIDTH: usize = 24;
+const MIN_HEIGHT: usize = 12;
+/// Maps that enemies can't get through are thrown away and generated again, up
+/// to this many times.
+const MAX_ATTEMPTS: usize = 16;
+
+/// Possible errors that can be produced when generating a map.
+#[derive(Debug, Error)]
+pub enum MapGenError {
+    #[error("Couldn't connect every spawner to every home in {MAX_ATTEMPTS} attempts")]
+    Unreachable,
+}
+
+/// Parameters for [`generate`].
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MapGenParams {
+    pub width: usize,
+    pub height: usize,
+    /// How much of the map, from the left edge, is covered by stone.
+    pub stone_fraction: f32,
+    /// Veins of hidden crystal and metal in the stone.
+    pub ore_veins: usize,
+    pub mountains: usize,
+    pub forests: usize,
+    pub grass_patches: usize,
+    pub rivers: usize,
+    pub homes: usize,
+    pub spawners: usize,
+}
+impl Default for MapGenParams {
+    fn default() -> Self {
+        Self {
+            width: 96,
+            height: 60,
+            stone_fraction: 0.35,
+            ore_veins: 16,
+            mountains: 4,
+            forests: 6,
+            grass_patches: 8,
+            rivers: 1,
+            homes: 4,
+            spawners: 3,
+        }
+    }
+}
+
+/// Generates a map from a seed. The same seed and parameters always produce the
+/// same map, on every platform.
+///
+/// Stone fills the left side of the map, with homes along its edge. Spawners are
+/// placed along the right edge and connected to every home by roads. The result
+/// is checked with the enemies' own cost functions, and regenerated if any kind
+/// of enemy couldn't reach every home.
+pub fn generate(seed: u64, params: &MapGenParams) -> Result<Map, MapGenError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    // Later attempts carry on with the same random numbers, so they're just as
+    // repeatable as the first.
+    for _ in 0..MAX_ATTEMPTS {
+        let map = attempt(&mut rng, params);
+        if connected(&map) {
+            return Ok(map);
+        }
+    }
+
+    Err(MapGenError::Unreachable)
+}
+
+/// Whether enemies of every cost class can walk from every spawner to every tile
+/// next to every home.
+fn connected(map: &Map) -> bool {
+    let homes = map.homes();
+
+    EnemyCostClass::iter().all(|class| {
+        let cost_fn = enemy_cost_fn(map, class);
+
+        map.spawners().iter().all(|(spawner, _)| {
+            let reachable = bfs_reach(*spawner, |pos| {
+                NeighborCostIter::new(*pos, &cost_fn).map(|(pos, _)| pos)
+            })
+            .collect::<HashSet<_>>();
+
+            homes.iter().all(|home| {
+                let mut targets = NeighborCostIter::new(*home, &cost_fn).peekable();
+                targets.peek().is_some() && targets.all(|(pos, _)| reachable.contains(&pos))
+            })
+        })
+    })
+}
+
+/// Samples a `usize` from `range` by way of `u32`, because `usize` ranges are
+/// sampled differently on 32-bit platforms such as wasm.
+fn gen_usize(rng: &mut ChaCha8Rng, range: std::ops::Range<usize>) -> usize {
+    rng.gen_range(range.start as u32..range.end as u32) as usize
+}
+
+/// Steps `value` by -1, 0 or 1.
+fn wander(rng: &mut ChaCha8Rng, value: isize) -> isize {
+    value + rng.gen_range(-1..=1i32) as isize
+}
+
+fn attempt(rng: &mut ChaCha8Rng, params: &MapGenParams) -> Map {
+    let width = params.width.max(MIN_WIDTH);
+    let height = params.height.max(MIN_HEIGHT);
+
+    let mut map = Map::new(height, width);
+    map.0.fill(TileKind::Dirt);
+
+    let stone_edges = stone(&mut map, rng, params.stone_fraction);
+
+    for _ in 0..params.ore_veins {
+        let y = gen_usize(rng, 0..height);
+        let x = gen_usize(rng, 0..stone_edges[y].max(1));
+        ore_vein(&mut map, rng, TilePos { x, y });
+    }
+
+    let open_area = |rng: &mut ChaCha8Rng, y: usize| gen_usize(rng, stone_edges[y]..width);
+
+    for _ in 0..params.mountains {
+        let y = gen_usize(rng, 0..height);
+        let x = gen_usize(rng, 0..width);
+        let radius = rng.gen_range(2.0..4.5);
+        blob(
+            &mut map,
+            rng,
+            TilePos { x, y },
+            radius,
+            |tile, dist| match (tile, dist < radius / 2.) {
+                (TileKind::Dirt | TileKind::Stone, true) => Some(TileKind::Peak),
+                (TileKind::Dirt | TileKind::Stone, false) => Some(TileKind::Mountain),
+                _ => None,
+            },
+        );
+    }
+
+    for _ in 0..params.forests {
+        let y = gen_usize(rng, 0..height);
+        let x = open_area(rng, y);
+        let radius = rng.gen_range(2.0..6.0);
+        blob(&mut map, rng, TilePos { x, y }, radius, |tile, _| {
+            (tile == TileKind::Dirt).then_some(TileKind::Forest)
+        });
+    }
+
+    for _ in 0..params.grass_patches {
+        let y = gen_usize(rng, 0..height);
+        let x = open_area(rng, y);
+        let radius = rng.gen_range(2.0..5.0);
+        let kind = *[TileKind::GrassA, TileKind::GrassB].choose(rng).unwrap();
+        blob(&mut map, rng, TilePos { x, y }, radius, |tile, _| {
+            (tile == TileKind::Dirt).then_some(kind)
+        });
+    }
+
+    for _ in 0..params.rivers {
+        river(&mut map, rng, &stone_edges);
+    }
+
+    let homes = homes(&mut map, rng, &stone_edges, params.homes.max(1));
+    let spawners = spawners(&mut map, params.spawners.max(1));
+
+    // Connect each spawner to its nearest home, and the homes to each other, so
+    // that every home can be reached from every spawner.
+    for spawner in &spawners {
+        let home = homes
+            .iter()
+            .min_by_key(|home| heuristic(**home, *spawner))
+            .unwrap();
+        road(&mut map, *spawner, *home);
+    }
+    for pair in homes.windows(2) {
+        road(&mut map, pair[0], pair[1]);
+    }
+
+    map
+}
+
+/// Fills the left side of the map with stone, returning the x position where the
+/// stone ends in each row.
+fn stone(map: &mut Map, rng: &mut ChaCha8Rng, fraction: f32) -> Vec<usize> {
+    let width = map.0.cols();
+    let base = (width as f32 * fraction.clamp(0., 0.5)) as isize;
+    let mut edge = base;
+    let mut edges = vec![];
+
+    for y in 0..map.0.rows() {
+        edge = wander(rng, edge).clamp(base - 3, base + 3).max(0);
+
+        for x in 0..edge as usize {
+            map.0[(y, x)] = TileKind::Stone;
+        }
+
+        edges.push(edge as usize);
+    }
+
+    edges
+}
+
+/// Turns a short, winding line of stone starting at `start` into hidden ore.
+fn ore_vein(map: &mut Map, rng: &mut ChaCha8Rng, start: TilePos) {
+    let kind = *[TileKind::CrystalHidden, TileKind::MetalHidden]
+        .choose(rng)
+        .unwrap();
+
+    let mut pos: (isize, isize) = start.into();
+
+    for _ in 0..rng.gen_range(3..=8u32) {
+        if let Some(tile) = map.0.get_mut(pos.1, pos.0) {
+            if *tile == TileKind::Stone {
+                *tile = kind;
+            }
+        }
+
+        let (dx, dy) = NEIGHBORS.choose(rng).unwrap();
+        pos = (pos.0 + dx, pos.1 + dy);
+    }
+}
+
+/// Replaces the tiles in a roughly circular area with the tile kind returned by
+/// `replace`, which is given the existing tile and its distance from the center.
+fn blob(
+    map: &mut Map,
+    rng: &mut ChaCha8Rng,
+    center: TilePos,
+    radius: f32,
+    replace: impl Fn(TileKind, f32) -> Option<TileKind>,
+) {
+    let r = radius.ceil() as isize;
+
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let dist = ((dx * dx + dy * dy) as f32).sqrt();
+            // Roughen the edges.
+            if dist + rng.gen_range(0.0..1.0) > radius {
+                continue;
+            }
+
+            let (x, y) = (center.x as isize + dx, center.y as isize + dy);
+            let Some(tile) = map.0.get_mut(y, x) else {
+                continue;
+            };
+
+            if let Some(kind) = replace(*tile, dist) {
+                *tile = kind;
+            }
+        }
+    }
+}
+
+/// Adds a river that winds from the top of the map to the bottom through the
+/// open area.
+fn river(map: &mut Map, rng: &mut ChaCha8Rng, stone_edges: &[usize]) {
+    let width = map.0.cols();
+    let min_x = stone_edges.iter().max().unwrap() + 10;
+    let max_x = width - 4;
+    if min_x >= max_x {
+        return;
+    }
+
+    let mut x = gen_usize(rng, min_x..max_x);
+
+    for y in (0..map.0.rows()).rev() {
+        x = wander(rng, x as isize).clamp(min_x as isize, max_x as isize) as usize;
+
+        map.0[(y, x)] = TileKind::River;
+        map.0[(y, x + 1)] = TileKind::River;
+    }
+}
+
+/// Places homes along the edge of the stone, evenly spread from top to bottom.
+fn homes(map: &mut Map, rng: &mut ChaCha8Rng, stone_edges: &[usize], count: usize) -> Vec<TilePos> {
+    let height = map.0.rows();
+    let mut homes = vec![];
+
+    for i in 0..count {
+        let y = ((i + 1) * height / (count + 1)).clamp(1, height - 2);
+        let x = stone_edges[y] + gen_usize(rng, 2..6);
+        let home = TilePos { x, y };
+
+        // Clear the area around the home so that it can always be reached.
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let tile = &mut map.0[((y as isize + dy) as usize, (x as isize + dx) as usize)];
+                if *tile != TileKind::Home {
+                    *tile = TileKind::Dirt;
+                }
+            }
+        }
+        map.0[(y, x)] = TileKind::Home;
+
+        homes.push(home);
+    }
+
+    homes
+}
+
+/// Places spawners along the right edge of the map, evenly spread from top to
+/// bottom.
+fn spawners(map: &mut Map, count: usize) -> Vec<TilePos> {
+    let (width, height) = (map.0.cols(), map.0.rows());
+    let mut spawners = vec![];
+
+    for i in 0..count {
+        let y = ((i + 1) * height / (count + 1)).clamp(0, height - 1);
+        let spawner = TilePos { x: width - 1, y };
+
+        map.0[(y, spawner.x)] = TileKind::Spawn;
+
+        spawners.push(spawner);
+    }
+
+    spawners
+}
+
+/// Builds a road between two tiles, bridging any rivers along the way. Existing
+/// roads are reused where possible, so roads join up into networks.
+fn road(map: &mut Map, from: TilePos, to: TilePos) {
+    let cost_fn = |pos: (isize, isize)| -> isize {
+        let Some(tile) = map.0.get(pos.1, pos.0) else {
+            return -1;
+        };
+
+        match tile {
+            _ if TilePos::from(pos) == to => 1,
+            TileKind::Road | TileKind::Bridge => 1,
+            TileKind::Dirt | TileKind::GrassA | TileKind::GrassB => 3,
+            TileKind::Forest | TileKind::River => 6,
+            TileKind::Home | TileKind::Spawn => -1,
+            _ => 12,
+        }
+    };
+
+    let Some((path, _)) = astar(
+        &from,
+        |p| NeighborCostIter::new(*p, cost_fn),
+        |p| heuristic(*p, to),
+        |p| *p == to,
+    ) else {
+        return;
+    };
+
+    for pos in &path[1..path.len() - 1] {
+        let tile = &mut map.0[(pos.y, pos.x)];
+        *tile = match tile {
+            TileKind::River | TileKind::Bridge => TileKind::Bridge,
+            _ => TileKind::Road,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_map() {
+        let params = MapGenParams::default();
+
+        let first = generate(42, &params).unwrap();
+        let second = generate(42, &params).unwrap();
+        let other = generate(43, &params).unwrap();
+
+        assert!(first.0 == second.0);
+        assert!(first.0 != other.0);
+    }
+
+    #[test]
+    fn spawners_reach_homes() {
+        let params = MapGenParams {
+            spawners: 5,
+            forests: 12,
+            rivers: 2,
+            ..MapGenParams::default()
+        };
+
+        for seed in 0..32 {
+            let map = generate(seed, &params).unwrap();
+
+            assert_eq!(map.spawners().len(), params.spawners, "seed {seed}");
+            assert_eq!(map.homes().len(), params.homes, "seed {seed}");
+            assert!(connected(&map), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn walled_off_home_is_not_connected() {
+        let mut map = generate(7, &MapGenParams::default()).unwrap();
+
+        let home = map.homes()[0];
+        for (dx, dy) in NEIGHBORS {
+            let pos = TilePos::from((dx, dy) + home);
+            map.0[(pos.y, pos.x)] = TileKind::Stone;
+        }
+
+        assert!(!connected(&map));
+    }
+}
@@ -17,6 +17,11 @@ impl Default for Speed {
 
 #[derive(Component, Default)]
 pub struct MovingProgress(f32);
+impl MovingProgress {
+    pub fn reset(&mut self) {
+        self.0 = 0.;
+    }
+}
 
 pub struct MovementPlugin;
 impl Plugin for MovementPlugin {
@@ -28,7 +33,7 @@ impl Plugin for MovementPlugin {
     }
 }
 
-fn movement(
+pub fn movement(
     mut commands: Commands,
     mut query: Query<(
         Entity,
@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    currency::Currency,
+    endless::GameMode,
+    enemy::EnemyKind,
+    hit_points::HitPoints,
+    home::Home,
+    level::{LevelConfig, LevelHandle, LevelReloadedEvent},
+    spawner::SpawnerStates,
+    stats::Stats,
+    waves::Waves,
+    GameState,
+};
+
+pub struct ObjectivePlugin;
+impl Plugin for ObjectivePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Objectives>()
+            .add_systems(OnEnter(GameState::Playing), init)
+            .add_systems(
+                Update,
+                (init.run_if(on_event::<LevelReloadedEvent>), update)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Something the player has to do to win a level, or can do for bonus points.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Objective {
+    /// Defeat every wave. This is the only objective of levels that don't have any.
+    SurviveWaves,
+    /// Survive for this many seconds after enemies start spawning.
+    SurviveFor(f32),
+    /// Mine this many stone, metal or crystal tiles.
+    Mine(usize),
+    MineCrystal(usize),
+    MineMetal(usize),
+    BuildTowers(usize),
+    DefeatEnemies(usize),
+    /// Have at least this much currency at once.
+    Hoard(Currency),
+    /// Never let the total hit points of all homes drop below this. This is
+    /// complete for as long as it holds, so a level that requires it should also
+    /// require something else.
+    KeepHomeHitPoints(u32),
+}
+impl Objective {
+    /// Whether the objective is complete for as long as something holds, rather
+    /// than once it's been done.
+    pub fn is_kept(&self) -> bool {
+        matches!(self, Self::KeepHomeHitPoints(_))
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::SurviveWaves => "Survive every wave".to_string(),
+            Self::SurviveFor(secs) => format!("Survive for {secs}s"),
+            Self::Mine(num) => format!("Mine {num} tiles"),
+            Self::MineCrystal(num) => format!("Mine {num} crystal"),
+            Self::MineMetal(num) => format!("Mine {num} metal"),
+            Self::BuildTowers(num) => format!("Build {num} towers"),
+            Self::DefeatEnemies(num) => format!("Defeat {num} enemies"),
+            Self::Hoard(currency) => format!(
+                "Hoard {} stone, {} metal and {} crystal",
+                currency.stone, currency.metal, currency.crystal
+            ),
+            Self::KeepHomeHitPoints(hp) => format!("Keep {hp} home hit points"),
+        }
+    }
+}
+
+/// An optional objective that is worth points when completed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BonusObjective {
+    pub objective: Objective,
+    pub points: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObjectiveStatus {
+    #[default]
+    InProgress,
+    Complete,
+    Failed,
+}
+
+#[derive(Clone, Debug)]
+pub struct ObjectiveProgress {
+    pub objective: Objective,
+    pub status: ObjectiveStatus,
+    /// How far along the objective is, e.g. "3/10". Empty for objectives without
+    /// a count.
+    pub progress: String,
+    /// Bonus points awarded for completing the objective.
+    pub points: u32,
+}
+impl From<Objective> for ObjectiveProgress {
+    fn from(objective: Objective) -> Self {
+        Self {
+            objective,
+            status: ObjectiveStatus::InProgress,
+            progress: String::new(),
+            points: 0,
+        }
+    }
+}
+
+/// The objectives of the level being played.
+#[derive(Resource, Default)]
+pub struct Objectives {
+    pub required: Vec<ObjectiveProgress>,
+    pub bonus: Vec<ObjectiveProgress>,
+}
+impl Objectives {
+    /// Whether every required objective has been completed, winning the level.
+    ///
+    /// Objectives that are kept are complete from the start, so at least one
+    /// other objective has to be completed too.
+    pub fn complete(&self) -> bool {
+        self.required.iter().any(|o| !o.objective.is_kept())
+            && self
+                .required
+                .iter()
+                .all(|o| o.status == ObjectiveStatus::Complete)
+    }
+    /// Whether any required objective has been failed, losing the level.
+    pub fn failed(&self) -> bool {
+        self.required
+            .iter()
+            .any(|o| o.status == ObjectiveStatus::Failed)
+    }
+    pub fn bonus_points(&self) -> u32 {
+        self.bonus
+            .iter()
+            .filter(|o| o.status == ObjectiveStatus::Complete)
+            .map(|o| o.points)
+            .sum()
+    }
+}
+
+fn init(
+    mut commands: Commands,
+    levels: Res<Assets<LevelConfig>>,
+    level_handle: Res<LevelHandle>,
+    mode: Res<GameMode>,
+) {
+    let Some(level) = levels.get(&level_handle.0) else {
+        warn!("Couldn't find level when initializing Objectives.");
+        return;
+    };
+
+    // Endless mode can't be won.
+    let required = if *mode == GameMode::Endless {
+        vec![]
+    } else if level.objectives.is_empty() {
+        vec![Objective::SurviveWaves.into()]
+    } else {
+        level.objectives.iter().cloned().map(Into::into).collect()
+    };
+
+    let bonus = level
+        .bonus_objectives
+        .iter()
+        .map(|bonus| ObjectiveProgress {
+            points: bonus.points,
+            ..bonus.objective.clone().into()
+        })
+        .collect();
+
+    commands.insert_resource(Objectives { required, bonus });
+}
+
+/// Everything that objectives are checked against.
+struct Progress<'a> {
+    stats: &'a Stats,
+    currency: &'a Currency,
+    /// `None` until the homes have been spawned.
+    home_hit_points: Option<u32>,
+    waves_done: bool,
+}
+
+pub fn update(
+    mut objectives: ResMut<Objectives>,
+    stats: Res<Stats>,
+    currency: Res<Currency>,
+    waves: Res<Waves>,
+    spawners: Res<SpawnerStates>,
+    enemies: Query<(), With<EnemyKind>>,
+    homes: Query<&HitPoints, With<Home>>,
+) {
+    let progress = Progress {
+        stats: &stats,
+        currency: &currency,
+        home_hit_points: (!homes.is_empty()).then(|| homes.iter().map(|hp| hp.current).sum()),
+        waves_done: waves.current().is_none()
+            && spawners.states.iter().all(|s| s.remaining == 0)
+            && enemies.is_empty(),
+    };
+
+    // Only mark the objectives as changed when they actually change, so that the
+    // HUD isn't rebuilt every frame.
+    let mut changed = false;
+    let inner = objectives.bypass_change_detection();
+
+    for objective in inner.required.iter_mut().chain(inner.bonus.iter_mut()) {
+        // Completed and failed objectives stay that way, even if e.g. the currency
+        // for a hoard objective is spent afterwards. Home hit points are the
+        // exception, as they're complete until they drop too low.
+        let keep = objective.objective.is_kept();
+        match objective.status {
+            ObjectiveStatus::Failed => continue,
+            ObjectiveStatus::Complete if !keep => continue,
+            _ => {}
+        }
+
+        let (status, text) = check(&objective.objective, &progress);
+
+        if status != objective.status || text != objective.progress {
+            objective.status = status;
+            objective.progress = text;
+            changed = true;
+        }
+    }
+
+    if changed {
+        objectives.set_changed();
+    }
+}
+
+fn check(objective: &Objective, progress: &Progress) -> (ObjectiveStatus, String) {
+    let count = |current: usize, target: usize| {
+        let status = if current >= target {
+            ObjectiveStatus::Complete
+        } else {
+            ObjectiveStatus::InProgress
+        };
+        (status, format!("{}/{}", current.min(target), target))
+    };
+
+    match objective {
+        Objective::SurviveWaves => {
+            let status = if progress.waves_done {
+                ObjectiveStatus::Complete
+            } else {
+                ObjectiveStatus::InProgress
+            };
+            (status, String::new())
+        }
+        Objective::SurviveFor(secs) => {
+            let elapsed = progress.stats.elapsed.as_secs_f32();
+            let status = if elapsed >= *secs {
+                ObjectiveStatus::Complete
+            } else {
+                ObjectiveStatus::InProgress
+            };
+            (status, format!("{:.0}s", (secs - elapsed).max(0.)))
+        }
+        Objective::Mine(num) => count(progress.stats.mined, *num),
+        Objective::MineCrystal(num) => count(progress.stats.crystal_mined, *num),
+        Objective::MineMetal(num) => count(progress.stats.metal_mined, *num),
+        Objective::BuildTowers(num) => count(progress.stats.towers, *num),
+        Objective::DefeatEnemies(num) => count(progress.stats.kills, *num),
+        Objective::Hoard(target) => {
+            let status = if progress.currency.has(target) {
+                ObjectiveStatus::Complete
+            } else {
+                ObjectiveStatus::InProgress
+            };
+            (status, String::new())
+        }
+        Objective::KeepHomeHitPoints(min) => match progress.home_hit_points {
+            Some(hp) if hp < *min => (ObjectiveStatus::Failed, format!("{hp}")),
+            Some(hp) => (ObjectiveStatus::Complete, format!("{hp}")),
+            None => (ObjectiveStatus::InProgress, String::new()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete(objective: Objective) -> ObjectiveProgress {
+        ObjectiveProgress {
+            status: ObjectiveStatus::Complete,
+            ..objective.into()
+        }
+    }
+
+    #[test]
+    fn kept_objectives_alone_dont_win() {
+        let mut objectives = Objectives {
+            required: vec![complete(Objective::KeepHomeHitPoints(10))],
+            bonus: vec![],
+        };
+        assert!(!objectives.complete());
+
+        objectives.required.push(Objective::Mine(5).into());
+        assert!(!objectives.complete());
+
+        objectives.required[1].status = ObjectiveStatus::Complete;
+        assert!(objectives.complete());
+    }
+}
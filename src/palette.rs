@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::tilemap::TileKind;
+
+/// The palette used by maps that don't specify one.
+pub const DEFAULT_PALETTE: &str = "levels/default.palette.ron";
+
+/// Describes how the pixel colors of a map image are turned into tiles.
+#[derive(Deserialize, Clone, Default)]
+pub struct Palette {
+    pub colors: HashMap<[u8; 3], TileKind>,
+    /// Overrides for the built-in properties of particular tile kinds.
+    #[serde(default)]
+    pub tiles: TileProperties,
+}
+impl Palette {
+    pub fn tile_kind(&self, rgb: [u8; 3]) -> Option<TileKind> {
+        self.colors.get(&rgb).copied()
+    }
+}
+
+#[derive(Deserialize, Clone, Default, Debug)]
+pub struct TileProperty {
+    pub hit_points: Option<u32>,
+    pub diggable: Option<bool>,
+    pub buildable: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(transparent)]
+pub struct TileProperties(pub HashMap<TileKind, TileProperty>);
+impl TileProperties {
+    pub fn hit_points(&self, kind: TileKind) -> Option<u32> {
+        self.0
+            .get(&kind)
+            .and_then(|p| p.hit_points)
+            .or_else(|| kind.hit_points())
+    }
+    pub fn diggable(&self, kind: TileKind) -> bool {
+        self.0
+            .get(&kind)
+            .and_then(|p| p.diggable)
+            .unwrap_or_else(|| kind.diggable())
+    }
+    pub fn buildable(&self, kind: TileKind) -> bool {
+        self.0
+            .get(&kind)
+            .and_then(|p| p.buildable)
+            .unwrap_or_else(|| kind.buildable())
+    }
+}
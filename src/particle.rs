@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::Deserialize;
 
 use crate::{
     layer,
@@ -28,7 +29,7 @@ impl Default for ParticleRng {
     }
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Deserialize, Default, Debug, Clone, Copy)]
 #[require(Sprite, Velocity, Life)]
 pub enum ParticleKind {
     #[default]
@@ -39,6 +40,8 @@ pub enum ParticleKind {
     Crystal,
     Metal,
     Purple,
+    Blood,
+    Fire,
 }
 impl ParticleKind {
     fn color(&self) -> Color {
@@ -50,6 +53,8 @@ impl ParticleKind {
             Self::Crystal => Color::srgb(0.37, 0.69, 0.84),
             Self::Metal => Color::srgb(0.84, 0.73, 0.37),
             Self::Purple => Color::srgb(0.74, 0., 0.71),
+            Self::Blood => Color::srgb(0.72, 0.1, 0.1),
+            Self::Fire => Color::srgb(1.0, 0.5, 0.1),
         }
     }
 }
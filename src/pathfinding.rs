@@ -1,14 +1,187 @@
-use bevy::prelude::*;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use grid::Grid;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
 
 use crate::{
     critter::CritterKind,
-    enemy::EnemyKind,
+    hit_points::HitPoints,
+    home::Home,
     tilemap::{Map, TileKind, TilePos},
+    GameState,
 };
 
 pub struct PathfindingPlugin;
 impl Plugin for PathfindingPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlowFields>()
+            .add_systems(
+                Update,
+                update_flow_fields.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::GameOver), clear_flow_fields);
+    }
+}
+
+/// Groups enemies that can walk on the same tiles, and so can share a flow field.
+#[derive(Deserialize, EnumIter, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EnemyCostClass {
+    Ground,
+    /// Can also walk through forests.
+    Forest,
+}
+
+/// Leads enemies of one cost class from anywhere on the map to the nearest tile
+/// next to a living home.
+pub struct FlowField {
+    /// The cost of walking onto each tile, or `-1` if it can't be walked on.
+    tile_costs: Grid<isize>,
+    /// The cost of the cheapest path from each tile to a goal, or `None` if no goal
+    /// can be reached.
+    costs: Grid<Option<u32>>,
+}
+impl FlowField {
+    fn tile_costs(map: &Map, class: EnemyCostClass) -> Grid<isize> {
+        let cost_fn = enemy_cost_fn(map, class);
+
+        let mut tile_costs = Grid::init(map.0.rows(), map.0.cols(), -1);
+        for ((y, x), cost) in tile_costs.indexed_iter_mut() {
+            *cost = cost_fn((x as isize, y as isize));
+        }
+
+        tile_costs
+    }
+
+    /// Runs Dijkstra's algorithm outwards from every goal.
+    fn new(tile_costs: Grid<isize>, goals: &[TilePos]) -> Self {
+        let mut costs = Grid::init(tile_costs.rows(), tile_costs.cols(), None);
+        let mut queue = BinaryHeap::new();
+
+        for goal in goals {
+            costs[(goal.y, goal.x)] = Some(0);
+            queue.push(Reverse((0, *goal)));
+        }
+
+        let cost_fn = |pos: (isize, isize)| *tile_costs.get(pos.1, pos.0).unwrap_or(&-1);
+
+        while let Some(Reverse((cost, pos))) = queue.pop() {
+            if costs[(pos.y, pos.x)].is_some_and(|c| c < cost) {
+                continue;
+            }
+
+            // Enemies walking from a neighbor onto this tile pay this tile's cost.
+            let step = tile_costs[(pos.y, pos.x)] as u32;
+
+            for (neighbor, _) in NeighborCostIter::new(pos, cost_fn) {
+                let neighbor_cost = cost + step;
+                let current = &mut costs[(neighbor.y, neighbor.x)];
+                if current.is_none_or(|c| neighbor_cost < c) {
+                    *current = Some(neighbor_cost);
+                    queue.push(Reverse((neighbor_cost, neighbor)));
+                }
+            }
+        }
+
+        Self { tile_costs, costs }
+    }
+
+    /// The cost of the cheapest path from `pos` to a goal. `Some(0)` means that
+    /// `pos` is next to a living home.
+    pub fn cost(&self, pos: TilePos) -> Option<u32> {
+        self.costs.get(pos.y, pos.x).copied().flatten()
+    }
+
+    /// The neighbors of `pos` that are on a cheapest path to a goal.
+    pub fn next(&self, pos: TilePos) -> Vec<TilePos> {
+        let cost_fn = |pos: (isize, isize)| *self.tile_costs.get(pos.1, pos.0).unwrap_or(&-1);
+
+        let options = NeighborCostIter::new(pos, cost_fn)
+            .filter_map(|(neighbor, step)| Some((neighbor, self.cost(neighbor)? + step)))
+            .collect::<Vec<_>>();
+
+        let Some(min) = options.iter().map(|(_, cost)| *cost).min() else {
+            return vec![];
+        };
+
+        options
+            .into_iter()
+            .filter(|(_, cost)| *cost == min)
+            .map(|(neighbor, _)| neighbor)
+            .collect()
+    }
+}
+
+/// A flow field for each enemy cost class, leading to the living homes.
+#[derive(Resource, Default)]
+pub struct FlowFields {
+    fields: HashMap<EnemyCostClass, FlowField>,
+    /// The living homes that the fields were built for.
+    homes: Vec<TilePos>,
+}
+impl FlowFields {
+    pub fn get(&self, class: EnemyCostClass) -> Option<&FlowField> {
+        self.fields.get(&class)
+    }
+}
+
+/// Rebuilds the flow fields when a home dies, or when the map changes in a way
+/// that opens or blocks a path.
+pub fn update_flow_fields(
+    map_query: Query<Ref<Map>>,
+    home_query: Query<(&TilePos, &HitPoints), With<Home>>,
+    mut flow_fields: ResMut<FlowFields>,
+) {
+    let Ok(map) = map_query.single() else {
+        return;
+    };
+
+    let mut homes = home_query
+        .iter()
+        .filter(|(_, hp)| !hp.is_zero())
+        .map(|(pos, _)| *pos)
+        .collect::<Vec<_>>();
+    homes.sort_unstable();
+
+    let homes_changed = homes != flow_fields.homes;
+    if !homes_changed && !map.is_changed() && !flow_fields.fields.is_empty() {
+        return;
+    }
+
+    for class in EnemyCostClass::iter() {
+        let tile_costs = FlowField::tile_costs(&map, class);
+
+        // Workers change the map every time they hit a stone, which usually
+        // doesn't change where enemies can walk.
+        if !homes_changed
+            && flow_fields
+                .fields
+                .get(&class)
+                .is_some_and(|field| field.tile_costs == tile_costs)
+        {
+            continue;
+        }
+
+        let cost_fn = |pos: (isize, isize)| *tile_costs.get(pos.1, pos.0).unwrap_or(&-1);
+        let mut goals = homes
+            .iter()
+            .flat_map(|home| NeighborCostIter::new(*home, cost_fn).map(|(pos, _)| pos))
+            .collect::<Vec<_>>();
+        goals.sort_unstable();
+        goals.dedup();
+
+        flow_fields
+            .fields
+            .insert(class, FlowField::new(tile_costs, &goals));
+    }
+
+    flow_fields.homes = homes;
+}
+
+fn clear_flow_fields(mut flow_fields: ResMut<FlowFields>) {
+    *flow_fields = FlowFields::default();
 }
 
 #[derive(Component)]
@@ -35,18 +208,15 @@ impl PathState {
     }
 }
 
-pub fn enemy_cost_fn(map: &Map, kind: EnemyKind) -> impl '_ + Fn((isize, isize)) -> isize {
+pub fn enemy_cost_fn(map: &Map, class: EnemyCostClass) -> impl '_ + Fn((isize, isize)) -> isize {
     move |pos| {
         let Some(tile) = map.0.get(pos.1, pos.0) else {
             return -1;
         };
 
-        match (tile, kind) {
+        match (tile, class) {
             (TileKind::Dirt, _) => 5,
-            (
-                TileKind::Forest,
-                EnemyKind::Ent | EnemyKind::EntTwo | EnemyKind::EntThree | EnemyKind::EntFour,
-            )
+            (TileKind::Forest, EnemyCostClass::Forest)
             | (
                 TileKind::Road
                 | TileKind::Bridge
@@ -228,3 +398,64 @@ where
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: usize, y: usize) -> TilePos {
+        TilePos { x, y }
+    }
+
+    #[test]
+    fn costs_add_up_along_a_corridor() {
+        let tile_costs = Grid::from_vec(vec![1, 1, 5, 1], 4);
+        let field = FlowField::new(tile_costs, &[pos(0, 0)]);
+
+        assert_eq!(field.cost(pos(0, 0)), Some(0));
+        assert_eq!(field.cost(pos(1, 0)), Some(1));
+        assert_eq!(field.cost(pos(2, 0)), Some(2));
+        // Walking off the expensive tile costs 5.
+        assert_eq!(field.cost(pos(3, 0)), Some(7));
+
+        assert_eq!(field.next(pos(3, 0)), vec![pos(2, 0)]);
+    }
+
+    #[test]
+    fn goes_around_expensive_tiles() {
+        #[rustfmt::skip]
+        let tile_costs = Grid::from_vec(vec![
+            1, 1, 1,
+            1, 9, 1,
+            1, 1, 1,
+        ], 3);
+        let field = FlowField::new(tile_costs, &[pos(0, 1)]);
+
+        assert_eq!(field.cost(pos(2, 1)), Some(4));
+
+        let mut next = field.next(pos(2, 1));
+        next.sort_unstable();
+        assert_eq!(next, vec![pos(2, 0), pos(2, 2)]);
+    }
+
+    #[test]
+    fn leads_to_the_nearest_goal() {
+        let tile_costs = Grid::from_vec(vec![1; 7], 7);
+        let field = FlowField::new(tile_costs, &[pos(0, 0), pos(6, 0)]);
+
+        assert_eq!(field.cost(pos(2, 0)), Some(2));
+        assert_eq!(field.next(pos(2, 0)), vec![pos(1, 0)]);
+        assert_eq!(field.cost(pos(4, 0)), Some(2));
+        assert_eq!(field.next(pos(4, 0)), vec![pos(5, 0)]);
+    }
+
+    #[test]
+    fn walls_block_the_way() {
+        let tile_costs = Grid::from_vec(vec![1, -1, 1], 3);
+        let field = FlowField::new(tile_costs, &[pos(0, 0)]);
+
+        assert_eq!(field.cost(pos(1, 0)), None);
+        assert_eq!(field.cost(pos(2, 0)), None);
+        assert!(field.next(pos(2, 0)).is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    currency::Currency,
+    game::Won,
+    hit_points::HitPoints,
+    home::Home,
+    level::{LevelConfig, LevelHandle},
+    objective::Objectives,
+    settings::{DifficultySetting, PersonalBest, ProgressSetting},
+    stats::Stats,
+    GameState,
+};
+
+/// Points for each home hit point that is left at the end of a level.
+pub const HOME_HIT_POINT_SCORE: u32 = 10;
+/// Points for each second that a level is finished under its par time.
+pub const TIME_SCORE: u32 = 5;
+/// Used for levels that don't set a par time.
+pub const DEFAULT_PAR_TIME: f32 = 600.;
+
+pub struct ScorePlugin;
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), record)
+            .add_systems(OnExit(GameState::GameOver), cleanup);
+    }
+}
+
+/// Per-level scoring settings.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Scoring {
+    /// The scores needed for one, two and three stars. Levels without these don't
+    /// award stars.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stars: Option<[u32; 3]>,
+    /// Finishing the level faster than this many seconds earns points. The clock
+    /// starts when enemies start spawning. Defaults to [`DEFAULT_PAR_TIME`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub par_time: Option<f32>,
+}
+impl Scoring {
+    pub fn par_time(&self) -> f32 {
+        self.par_time.unwrap_or(DEFAULT_PAR_TIME)
+    }
+
+    /// The points for finishing the level after `elapsed`, which is [`Stats::elapsed`]
+    /// and so leaves out the time before enemies start spawning.
+    pub fn time_score(&self, elapsed: Duration) -> u32 {
+        (self.par_time() - elapsed.as_secs_f32()).max(0.) as u32 * TIME_SCORE
+    }
+
+    /// The number of stars that a score is worth.
+    pub fn stars(&self, score: u32) -> u8 {
+        self.stars.map_or(0, |thresholds| {
+            thresholds.iter().filter(|t| score >= **t).count() as u8
+        })
+    }
+}
+
+/// The score of a level that was just won, and where the points came from.
+#[derive(Resource, Debug)]
+pub struct Score {
+    pub home_hit_points: u32,
+    pub time: u32,
+    pub resources: u32,
+    pub bonus: u32,
+    pub multiplier: f32,
+    pub total: u32,
+    pub stars: u8,
+    /// Whether the level awards stars at all.
+    pub has_stars: bool,
+    /// The best score for the level on this difficulty before this one.
+    pub previous_best: Option<PersonalBest>,
+}
+impl Score {
+    pub fn is_personal_best(&self) -> bool {
+        self.previous_best
+            .is_none_or(|best| self.total > best.score || self.stars > best.stars)
+    }
+}
+
+pub fn resource_score(currency: &Currency) -> u32 {
+    currency.stone + currency.metal * 5 + currency.crystal * 5
+}
+
+pub fn difficulty_multiplier(difficulty: &DifficultySetting) -> f32 {
+    match difficulty {
+        DifficultySetting::Normal => 1.,
+        DifficultySetting::Hard => 1.5,
+        DifficultySetting::Impossible => 2.,
+    }
+}
+
+pub fn record(
+    mut commands: Commands,
+    won: Res<Won>,
+    stats: Res<Stats>,
+    currency: Res<Currency>,
+    objectives: Res<Objectives>,
+    difficulty: Res<DifficultySetting>,
+    homes: Query<&HitPoints, With<Home>>,
+    levels: Res<Assets<LevelConfig>>,
+    level_handle: Res<LevelHandle>,
+    asset_server: Res<AssetServer>,
+    mut progress: ResMut<ProgressSetting>,
+) {
+    // Only winning a level is worth points.
+    if !won.0 {
+        commands.remove_resource::<Score>();
+        return;
+    }
+
+    let Some(level) = levels.get(&level_handle.0) else {
+        warn!("Couldn't find level when calculating score.");
+        return;
+    };
+
+    let home_hit_points = homes.iter().map(|hp| hp.current).sum::<u32>() * HOME_HIT_POINT_SCORE;
+    let time = level.scoring.time_score(stats.elapsed);
+    let resources = resource_score(&currency);
+    let bonus = objectives.bonus_points();
+    let multiplier = difficulty_multiplier(&difficulty);
+
+    let total = ((home_hit_points + time + resources + bonus) as f32 * multiplier).round() as u32;
+    let stars = level.scoring.stars(total);
+
+    let key = asset_server
+        .get_path(level_handle.0.id())
+        .map(|path| path.to_string());
+    let previous_best = key.as_ref().and_then(|key| progress.best(key, &difficulty));
+
+    let score = Score {
+        home_hit_points,
+        time,
+        resources,
+        bonus,
+        multiplier,
+        total,
+        stars,
+        has_stars: level.scoring.stars.is_some(),
+        previous_best,
+    };
+
+    if let Some(key) = key {
+        let level_progress = progress.levels.entry(key).or_default();
+        level_progress.completed = true;
+
+        if score.is_personal_best() {
+            let best = previous_best.unwrap_or_default();
+            level_progress.best.insert(
+                difficulty.clone(),
+                PersonalBest {
+                    score: total.max(best.score),
+                    stars: stars.max(best.stars),
+                },
+            );
+        }
+    } else {
+        warn!("Couldn't find level path when saving progress.");
+    }
+
+    commands.insert_resource(score);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Score>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_score_counts_seconds_under_par() {
+        let scoring = Scoring {
+            par_time: Some(100.),
+            ..default()
+        };
+
+        assert_eq!(scoring.time_score(Duration::from_secs(40)), 60 * TIME_SCORE);
+        assert_eq!(scoring.time_score(Duration::from_secs(100)), 0);
+        assert_eq!(scoring.time_score(Duration::from_secs(300)), 0);
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::Display;
 
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 use bevy_simple_prefs::{Prefs, PrefsPlugin};
 
+use crate::level::LevelCatalog;
+
 pub struct SettingsPlugin;
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
@@ -30,7 +32,7 @@ impl Default for SfxSetting {
         Self(50)
     }
 }
-#[derive(Resource, Reflect, Clone, Eq, PartialEq, Debug, Default)]
+#[derive(Resource, Reflect, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub enum DifficultySetting {
     #[default]
     Normal,
@@ -38,6 +40,14 @@ pub enum DifficultySetting {
     Impossible,
 }
 impl DifficultySetting {
+    /// The hit points that an enemy with `hp` hit points actually has.
+    pub fn scale_hp(&self, hp: u32) -> u32 {
+        match self {
+            Self::Hard => hp,
+            Self::Normal => ((hp as f32 * 0.75).floor() as u32).max(1),
+            Self::Impossible => ((hp as f32 * 1.25).floor() as u32).max(1),
+        }
+    }
     pub fn next(&self) -> Self {
         match self {
             Self::Normal => Self::Hard,
@@ -106,11 +116,107 @@ impl Display for ParticlesSetting {
 #[derive(Resource, Reflect, Clone, Eq, PartialEq, Debug, Default)]
 pub struct TutorialFinishedSetting(pub bool);
 
+#[derive(Reflect, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct PersonalBest {
+    pub score: u32,
+    pub stars: u8,
+}
+#[derive(Reflect, Clone, Eq, PartialEq, Debug, Default)]
+#[reflect(Default)]
+pub struct LevelProgress {
+    pub completed: bool,
+    /// The best score and stars for each difficulty the level was won on.
+    pub best: HashMap<DifficultySetting, PersonalBest>,
+    /// The furthest wave reached in endless mode on each difficulty.
+    pub best_wave: HashMap<DifficultySetting, usize>,
+}
+/// The player's progress through the levels, keyed by the level's path.
+#[derive(Resource, Reflect, Clone, Eq, PartialEq, Debug, Default)]
+#[reflect(Default)]
+pub struct ProgressSetting {
+    pub levels: HashMap<String, LevelProgress>,
+}
+impl ProgressSetting {
+    pub fn completed(&self, path: &str) -> bool {
+        self.levels.get(path).is_some_and(|level| level.completed)
+    }
+    pub fn best(&self, path: &str, difficulty: &DifficultySetting) -> Option<PersonalBest> {
+        self.levels.get(path)?.best.get(difficulty).copied()
+    }
+    pub fn best_wave(&self, path: &str, difficulty: &DifficultySetting) -> Option<usize> {
+        self.levels.get(path)?.best_wave.get(difficulty).copied()
+    }
+    /// Levels are unlocked in order, by completing the one before them.
+    pub fn unlocked(&self, catalog: &LevelCatalog, index: usize) -> bool {
+        index == 0
+            || catalog
+                .levels
+                .get(index - 1)
+                .is_some_and(|previous| self.completed(&previous.path))
+    }
+}
+
+// Fields that are missing from older save files are filled in with their defaults,
+// so new ones can be added without losing the player's settings.
 #[derive(Prefs, Reflect, Default)]
+#[reflect(Default)]
 struct Settings {
     sfx: SfxSetting,
     music: MusicSetting,
     particles: ParticlesSetting,
     difficulty: DifficultySetting,
     tutorial_finished: TutorialFinishedSetting,
+    progress: ProgressSetting,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LevelInfo;
+
+    #[test]
+    fn difficulty_scales_hp() {
+        assert_eq!(DifficultySetting::Normal.scale_hp(10), 7);
+        assert_eq!(DifficultySetting::Hard.scale_hp(10), 10);
+        assert_eq!(DifficultySetting::Impossible.scale_hp(10), 12);
+    }
+
+    #[test]
+    fn scaled_hp_is_at_least_one() {
+        assert_eq!(DifficultySetting::Normal.scale_hp(1), 1);
+        assert_eq!(DifficultySetting::Impossible.scale_hp(0), 1);
+    }
+
+    #[test]
+    fn old_save_files_still_load() {
+        // Saved before level progress was stored.
+        let old = "(
+            sfx: (30),
+            music: (40),
+            particles: Low,
+            difficulty: Hard,
+            tutorial_finished: (true),
+        )";
+
+        let settings = bevy_simple_prefs::deserialize::<Settings>(old).unwrap();
+
+        assert_eq!(*settings.sfx, 30);
+        assert_eq!(*settings.music, 40);
+        assert_eq!(settings.particles, ParticlesSetting::Low);
+        assert_eq!(settings.difficulty, DifficultySetting::Hard);
+        assert!(settings.tutorial_finished.0);
+        assert_eq!(settings.progress, ProgressSetting::default());
+
+        let catalog = LevelCatalog {
+            levels: ["1", "2"]
+                .map(|name| LevelInfo {
+                    name: name.to_string(),
+                    description: String::new(),
+                    path: format!("levels/{name}.level.ron"),
+                })
+                .into(),
+        };
+        assert!(settings.progress.unlocked(&catalog, 0));
+        assert!(!settings.progress.unlocked(&catalog, 1));
+    }
 }
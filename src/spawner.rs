@@ -1,29 +1,44 @@
 use std::time::Duration;
 
 use bevy::{audio::Volume, prelude::*};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    currency::Currency,
     enemy::{EnemyKind, SpawnEnemyEvent},
+    enemy_table::Enemies,
     settings::SfxSetting,
     sound::SoundAssets,
+    stats::Stats,
     tilemap::{AtlasHandle, TilePos, SCALE, TILE_SIZE},
     ui::{slice_image_mode, UiAssets, TITLE_TEXT},
-    waves::{WaveStartEvent, Waves},
+    waves::{Wave, WaveStartEvent, Waves},
     GameState,
 };
 
-const SPAWNER_UI_SIZE: Vec2 = Vec2::new(64., 64.);
+const SPAWNER_UI_SIZE: Vec2 = Vec2::new(80., 88.);
+
+/// Seconds of delay skipped for each stone given when calling a wave early.
+const EARLY_SECONDS_PER_STONE: f32 = 2.;
+/// Seconds of delay skipped for each metal given when calling a wave early.
+const EARLY_SECONDS_PER_METAL: f32 = 20.;
 
 pub struct SpawnerPlugin;
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<WaveStartEvent>()
+            .add_event::<CallWaveEarlyEvent>()
             .init_resource::<SpawnerStates>()
             .init_resource::<SpawningPaused>()
             .add_systems(
                 Update,
-                (init, spawn, first_wave_audio).run_if(in_state(GameState::Playing)),
+                (
+                    init,
+                    call_early.run_if(on_event::<CallWaveEarlyEvent>),
+                    spawn,
+                    first_wave_audio,
+                )
+                    .run_if(in_state(GameState::Playing)),
             )
             .add_systems(
                 Update,
@@ -43,6 +58,9 @@ pub struct SpawnerUi(Entity);
 #[derive(Component)]
 pub struct SpawnerDelayText;
 
+#[derive(Component)]
+pub struct SpawnerBonusText;
+
 #[derive(Component)]
 pub struct SpawnerPortrait;
 
@@ -52,6 +70,11 @@ pub struct SpawnerContainer;
 #[derive(Component)]
 pub struct SpawnerIndex(pub usize);
 
+/// Skips the remaining delay of every spawner in the current wave, in exchange
+/// for a bonus.
+#[derive(Event)]
+pub struct CallWaveEarlyEvent;
+
 #[derive(Resource)]
 pub struct SpawningPaused(pub bool);
 impl Default for SpawningPaused {
@@ -60,19 +83,55 @@ impl Default for SpawningPaused {
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Spawn {
     pub spawner: usize,
     pub num: usize,
+    /// Seconds to wait after the spawn is triggered.
     pub delay: f32,
     pub interval: f32,
     pub hp: u32,
     pub kind: EnemyKind,
+    /// Tiles per second. Defaults to the enemy's usual speed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    /// Overrides the enemy table's damage for this spawn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub damage: Option<u32>,
+    /// Given to the player for each enemy defeated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Currency>,
+    /// Lets other spawns in the wave wait for this one with
+    /// [`SpawnTrigger::GroupFinished`]. Several spawns can share a group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "SpawnTrigger::is_wave_start")]
+    pub trigger: SpawnTrigger,
+}
+
+/// When a [`Spawn`] starts counting down its delay.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum SpawnTrigger {
+    #[default]
+    WaveStart,
+    /// Once every spawn in the group has spawned all of its enemies.
+    GroupFinished(String),
+    /// Once this many enemies have been defeated since the wave started.
+    Kills(usize),
+}
+impl SpawnTrigger {
+    pub fn is_wave_start(&self) -> bool {
+        *self == Self::WaveStart
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct SpawnerStates {
     pub states: Vec<SpawnerState>,
+    /// Forces the next wave to start, if the wave has a timeout.
+    pub timeout: Option<Timer>,
+    /// The number of kills when the wave started.
+    pub kills_at_start: usize,
 }
 impl From<&Vec<Spawn>> for SpawnerStates {
     fn from(value: &Vec<Spawn>) -> Self {
@@ -85,11 +144,51 @@ impl From<&Vec<Spawn>> for SpawnerStates {
         states
     }
 }
+impl SpawnerStates {
+    fn start(&mut self, wave: Option<&Wave>, kills: usize) {
+        self.states.clear();
+        self.timeout = None;
+        self.kills_at_start = kills;
+
+        if let Some(wave) = wave {
+            self.states
+                .extend(wave.spawns.iter().cloned().map(Into::into));
+            self.timeout = wave
+                .timeout
+                .map(|secs| Timer::from_seconds(secs, TimerMode::Once));
+        }
+    }
+
+    /// The longest delay left before a spawner starts spawning, if the wave can
+    /// be called early.
+    pub fn time_to_wave(&self) -> Option<f32> {
+        self.states
+            .iter()
+            .filter(|s| s.triggered && s.remaining > 0 && !s.delay_timer.finished())
+            .map(|s| s.delay_timer.remaining_secs())
+            .reduce(f32::max)
+    }
+
+    /// Whether a spawn's trigger has happened.
+    fn triggered(&self, trigger: &SpawnTrigger, kills: usize) -> bool {
+        match trigger {
+            SpawnTrigger::WaveStart => true,
+            SpawnTrigger::GroupFinished(group) => self
+                .states
+                .iter()
+                .filter(|s| s.spawn.group.as_ref() == Some(group))
+                .all(|s| s.remaining == 0),
+            SpawnTrigger::Kills(num) => kills.saturating_sub(self.kills_at_start) >= *num,
+        }
+    }
+}
 
 pub struct SpawnerState {
     pub delay_timer: Timer,
     pub spawn_timer: Timer,
     pub remaining: usize,
+    /// Whether the spawn's trigger has happened and its delay is counting down.
+    pub triggered: bool,
     pub spawn: Spawn,
 }
 impl From<Spawn> for SpawnerState {
@@ -101,6 +200,7 @@ impl From<Spawn> for SpawnerState {
             delay_timer: Timer::from_seconds(spawn.delay, TimerMode::Once),
             spawn_timer,
             remaining: spawn.num,
+            triggered: false,
             spawn,
         }
     }
@@ -116,6 +216,7 @@ fn spawn(
     paused: Res<SpawningPaused>,
     sound_assets: Res<SoundAssets>,
     sfx_setting: Res<SfxSetting>,
+    stats: Res<Stats>,
 ) {
     if paused.0 {
         return;
@@ -125,8 +226,20 @@ fn spawn(
         return;
     }
 
+    for i in 0..states.states.len() {
+        if !states.states[i].triggered {
+            states.states[i].triggered =
+                states.triggered(&states.states[i].spawn.trigger, stats.kills);
+        }
+    }
+
+    let timed_out = states.timeout.as_mut().is_some_and(|timeout| {
+        timeout.tick(time.delta());
+        timeout.finished()
+    });
+
     for state in &mut states.states {
-        if state.remaining == 0 {
+        if state.remaining == 0 || !state.triggered {
             continue;
         }
 
@@ -146,6 +259,9 @@ fn spawn(
                 pos: *pos,
                 kind: state.spawn.kind,
                 hp: state.spawn.hp,
+                speed: state.spawn.speed,
+                damage: state.spawn.damage,
+                reward: state.spawn.reward.clone(),
             });
 
             state.remaining -= 1;
@@ -153,8 +269,13 @@ fn spawn(
     }
 
     let none_remaining = states.states.iter().all(|s| s.remaining == 0);
-    if none_remaining {
-        info!("Wave {}: All spawners have finished.", waves.current);
+    if none_remaining || timed_out {
+        if none_remaining {
+            info!("Wave {}: All spawners have finished.", waves.current);
+        } else {
+            info!("Wave {}: Timed out.", waves.current);
+            states.timeout = None;
+        }
         let next = waves.advance();
 
         if next.is_some() {
@@ -166,18 +287,49 @@ fn spawn(
     }
 }
 
-fn init(waves: Res<Waves>, mut states: ResMut<SpawnerStates>) {
-    if !waves.is_changed() {
+/// The bonus for calling a wave this many seconds early.
+pub fn early_bonus(secs: f32) -> Currency {
+    Currency {
+        stone: (secs / EARLY_SECONDS_PER_STONE) as u32,
+        metal: (secs / EARLY_SECONDS_PER_METAL) as u32,
+        crystal: 0,
+    }
+}
+
+fn call_early(
+    mut events: EventReader<CallWaveEarlyEvent>,
+    mut states: ResMut<SpawnerStates>,
+    mut currency: ResMut<Currency>,
+    paused: Res<SpawningPaused>,
+) {
+    events.clear();
+
+    if paused.0 {
         return;
     }
 
-    states.states.clear();
+    let Some(skipped) = states.time_to_wave() else {
+        return;
+    };
 
-    if let Some(wave) = waves.current() {
-        for spawn in wave.spawns.iter().cloned() {
-            states.states.push(spawn.into());
+    for state in &mut states.states {
+        if state.triggered {
+            let duration = state.delay_timer.duration();
+            state.delay_timer.set_elapsed(duration);
         }
     }
+
+    let bonus = early_bonus(skipped);
+    info!("Called wave early, skipping {skipped:.1}s for {bonus:?}.");
+    currency.add(&bonus);
+}
+
+fn init(waves: Res<Waves>, mut states: ResMut<SpawnerStates>, stats: Res<Stats>) {
+    if !waves.is_changed() {
+        return;
+    }
+
+    states.start(waves.current(), stats.kills);
 }
 
 fn add_spawner_ui(
@@ -237,6 +389,16 @@ fn add_spawner_ui(
                     TextColor(TITLE_TEXT),
                     SpawnerDelayText,
                 ));
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: 10.0,
+                        ..default()
+                    },
+                    TextColor(TITLE_TEXT),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    SpawnerBonusText,
+                ));
             })
             .id();
 
@@ -248,11 +410,15 @@ fn update_spawner_ui(
     query: Query<(&Transform, &SpawnerIndex, &SpawnerUi)>,
     mut ui_query: Query<(&mut Node, &Children), With<SpawnerContainer>>,
     mut ui_atlas_query: Query<&mut ImageNode, With<SpawnerPortrait>>,
-    mut ui_text_query: Query<&mut Text, With<SpawnerDelayText>>,
+    mut ui_text_query: Query<&mut Text, (With<SpawnerDelayText>, Without<SpawnerBonusText>)>,
+    mut ui_bonus_query: Query<&mut Text, With<SpawnerBonusText>>,
     spawners: Res<SpawnerStates>,
     camera_query: Query<(&Camera, &GlobalTransform, &Projection), With<Camera2d>>,
     spawning_paused: Res<SpawningPaused>,
+    enemies: Enemies,
 ) {
+    let bonus = spawners.time_to_wave().map(early_bonus);
+
     for (_, index, ui_entity) in &query {
         let Ok((mut container_node, children)) = ui_query.get_mut(ui_entity.0) else {
             continue;
@@ -267,7 +433,11 @@ fn update_spawner_ui(
             continue;
         };
 
-        if !spawning_paused.0 && state.remaining > 0 && !state.delay_timer.finished() {
+        if !spawning_paused.0
+            && state.triggered
+            && state.remaining > 0
+            && !state.delay_timer.finished()
+        {
             container_node.display = Display::Flex;
         } else {
             container_node.display = Display::None;
@@ -283,9 +453,20 @@ fn update_spawner_ui(
             continue;
         };
 
-        atlas.index = state.spawn.kind.atlas_index();
+        atlas.index = enemies.stats(state.spawn.kind).sprite;
 
         text.0 = format!("{:.1}", state.delay_timer.remaining_secs());
+
+        if let Some(mut bonus_text) = ui_bonus_query.iter_many_mut(children).fetch_next() {
+            // What calling the wave early would give the player.
+            bonus_text.0 = match &bonus {
+                Some(bonus) if bonus.metal > 0 => {
+                    format!("+{} stone\n+{} metal", bonus.stone, bonus.metal)
+                }
+                Some(bonus) if bonus.stone > 0 => format!("+{} stone", bonus.stone),
+                _ => String::new(),
+            };
+        }
     }
 
     let Ok((camera, camera_transform, Projection::Orthographic(projection))) =
@@ -343,15 +524,9 @@ fn cleanup(
         commands.entity(entity).despawn();
     }
 
-    states.states.clear();
-
     waves.reset();
 
-    if let Some(wave) = waves.current() {
-        for spawn in wave.spawns.iter().cloned() {
-            states.states.push(spawn.into());
-        }
-    }
+    states.start(waves.current(), 0);
 
     paused.0 = true;
 }
@@ -1,11 +1,22 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{
+    damage::{apply_damage, DamagedEvent},
+    enemy::EnemyKind,
+    spawner::SpawningPaused,
+    GameState,
+};
 
 pub struct StatsPlugin;
 impl Plugin for StatsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Stats>()
+            .add_systems(
+                Update,
+                (tick, count_damage.after(apply_damage)).run_if(in_state(GameState::Playing)),
+            )
             .add_systems(OnExit(GameState::GameOver), cleanup);
     }
 }
@@ -14,9 +25,66 @@ impl Plugin for StatsPlugin {
 pub struct Stats {
     pub kills: usize,
     pub mined: usize,
+    pub crystal_mined: usize,
+    pub metal_mined: usize,
     pub towers: usize,
+    pub workers_lost: usize,
+    /// Hit points taken from enemies.
+    pub damage_dealt: u64,
+    /// How long the level has been played for, not counting the time before
+    /// enemies start spawning.
+    pub elapsed: Duration,
+}
+
+fn tick(mut stats: ResMut<Stats>, time: Res<Time>, paused: Res<SpawningPaused>) {
+    if paused.0 {
+        return;
+    }
+
+    stats.elapsed += time.delta();
+}
+
+fn count_damage(
+    mut stats: ResMut<Stats>,
+    mut events: EventReader<DamagedEvent>,
+    query: Query<(), With<EnemyKind>>,
+) {
+    for event in events.read() {
+        if query.contains(event.target) {
+            stats.damage_dealt += event.amount as u64;
+        }
+    }
 }
 
 fn cleanup(mut commands: Commands) {
     commands.insert_resource(Stats::default());
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn tick_for(world: &mut World, secs: u64) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(secs));
+        world.run_system_once(tick).unwrap();
+    }
+
+    #[test]
+    fn clock_waits_for_spawning() {
+        let mut world = World::new();
+        world.init_resource::<Stats>();
+        world.init_resource::<Time>();
+        world.insert_resource(SpawningPaused(true));
+
+        tick_for(&mut world, 30);
+        assert_eq!(world.resource::<Stats>().elapsed, Duration::ZERO);
+
+        world.resource_mut::<SpawningPaused>().0 = false;
+        tick_for(&mut world, 2);
+        assert_eq!(world.resource::<Stats>().elapsed, Duration::from_secs(2));
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
     settings::ParticlesSetting,
     spawner::SpawningPaused,
     stats::Stats,
-    tilemap::{Map, TileEntities, TileKind, TilePos},
+    tilemap::{Map, MapChangedEvent, TileEntities, TileKind, TilePos},
     GameState,
 };
 
@@ -60,6 +60,7 @@ fn hit_events(
     mut commands: Commands,
     mut reader: EventReader<HitStoneEvent>,
     mut writer: EventWriter<RevealStoneEvent>,
+    mut changed_writer: EventWriter<MapChangedEvent>,
     mut query: Query<(&mut HitPoints, &TilePos, &mut TileKind, &mut Sprite)>,
     mut designations: ResMut<Designations>,
     mut tilemap_query: Query<(&mut Map, &TileEntities)>,
@@ -148,15 +149,20 @@ fn hit_events(
         if let Some(ref mut atlas) = sprite.texture_atlas {
             atlas.index = kind.atlas_index();
         }
-        map.0[(pos.y, pos.x)] = *kind;
+        if map.0[(pos.y, pos.x)] != *kind {
+            map.0[(pos.y, pos.x)] = *kind;
+            changed_writer.write(MapChangedEvent { pos: *pos });
+        }
 
         if hp.is_zero() {
             stats.mined += 1;
 
             if crystal {
                 currency.crystal += 1;
+                stats.crystal_mined += 1;
             } else if metal {
                 currency.metal += 1;
+                stats.metal_mined += 1;
             } else {
                 currency.stone += 1;
             }
@@ -0,0 +1,496 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    critter::CritterKind,
+    tilemap::{Map, TileKind, TilePos},
+};
+
+/// The high bits of a gid are used by Tiled to store flip and rotation flags.
+const GID_MASK: u32 = 0x0fff_ffff;
+
+/// A map exported from Tiled in its JSON format.
+///
+/// Only the parts of the format that the game uses are included.
+#[derive(Deserialize, Debug)]
+pub struct TiledMap {
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: f32,
+    pub tileheight: f32,
+    #[serde(default)]
+    pub orientation: String,
+    #[serde(default)]
+    pub infinite: bool,
+    pub layers: Vec<TiledLayer>,
+    #[serde(default)]
+    pub tilesets: Vec<TiledTilesetRef>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TiledLayer {
+    TileLayer {
+        name: String,
+        width: usize,
+        height: usize,
+        /// Missing for infinite maps, which store their tiles in chunks instead.
+        data: Option<TiledLayerData>,
+    },
+    ObjectGroup {
+        objects: Vec<TiledObject>,
+    },
+    Group {
+        layers: Vec<TiledLayer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum TiledLayerData {
+    Csv(Vec<u32>),
+    /// Base64 data, possibly compressed.
+    Encoded(serde::de::IgnoredAny),
+}
+
+/// A tileset used by a map, either embedded in the map or stored in an external
+/// `source` file.
+#[derive(Deserialize, Debug)]
+pub struct TiledTilesetRef {
+    pub firstgid: u32,
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub tileset: TiledTileset,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TiledTileset {
+    #[serde(default)]
+    pub tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TiledTile {
+    pub id: u32,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    /// Called "class" in the Tiled editor.
+    #[serde(default, rename = "type", alias = "class")]
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    /// Set for tile objects, which are positioned by their bottom left corner
+    /// rather than their top left.
+    pub gid: Option<u32>,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TiledProperty {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+fn property<'a>(properties: &'a [TiledProperty], name: &str) -> Option<&'a serde_json::Value> {
+    properties.iter().find(|p| p.name == name).map(|p| &p.value)
+}
+
+/// Possible errors that can be produced when converting a Tiled map.
+#[derive(Debug, Error)]
+pub enum TiledError {
+    #[error("Only orthogonal maps are supported, not \"{0}\"")]
+    Orientation(String),
+    #[error("Infinite maps are not supported")]
+    Infinite,
+    #[error("Layer \"{0}\" is compressed or encoded. Set the tile layer format to CSV.")]
+    Encoded(String),
+    #[error("Layer \"{layer}\" is {found:?} tiles, but the map is {expected:?}")]
+    LayerSize {
+        layer: String,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    #[error("Layer \"{layer}\" has {found} tiles, expected {expected}")]
+    LayerData {
+        layer: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Tile {0} is not in any tileset")]
+    MissingTileset(u32),
+    #[error("Tile {gid} has unknown tile kind \"{value}\"")]
+    UnknownTileKind { gid: u32, value: String },
+    #[error("Object {id} has unknown class \"{class}\"")]
+    UnknownObjectClass { id: u32, class: String },
+    #[error("Object {0} is outside of the map")]
+    ObjectOutOfBounds(u32),
+    #[error("Object {id} is missing the \"{property}\" property")]
+    MissingProperty { id: u32, property: &'static str },
+    #[error("Object {id} has invalid \"{property}\" property {value}")]
+    InvalidProperty {
+        id: u32,
+        property: &'static str,
+        value: String,
+    },
+}
+
+/// A tile whose tileset tile has no `kind` property.
+///
+/// `x` and `y` are the tile's coordinates as shown in Tiled, with the origin at
+/// the top left.
+#[derive(Debug, Clone)]
+pub struct UnmappedTile {
+    pub layer: String,
+    pub x: usize,
+    pub y: usize,
+    pub gid: u32,
+}
+impl Display for UnmappedTile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "layer \"{}\" ({}, {}) tile {}",
+            self.layer, self.x, self.y, self.gid
+        )
+    }
+}
+
+/// Builds a [`Map`] from a Tiled map, along with any tiles that don't have a tile
+/// kind. Those tiles are left as they were in lower layers.
+///
+/// Any external tilesets must already have been read into their
+/// [`TiledTilesetRef`].
+pub fn map_from_tiled(tiled: &TiledMap) -> Result<(Map, Vec<UnmappedTile>), TiledError> {
+    if !tiled.orientation.is_empty() && tiled.orientation != "orthogonal" {
+        return Err(TiledError::Orientation(tiled.orientation.clone()));
+    }
+    if tiled.infinite {
+        return Err(TiledError::Infinite);
+    }
+
+    let mut map = Map::new(tiled.height, tiled.width);
+    let mut unmapped = vec![];
+
+    let mut tile_layers = vec![];
+    let mut objects = vec![];
+    flatten_layers(&tiled.layers, &mut tile_layers, &mut objects);
+
+    for (name, width, height, data) in tile_layers {
+        if (width, height) != (tiled.width, tiled.height) {
+            return Err(TiledError::LayerSize {
+                layer: name.clone(),
+                expected: (tiled.width, tiled.height),
+                found: (width, height),
+            });
+        }
+
+        let data = match data {
+            Some(TiledLayerData::Csv(data)) => data,
+            Some(TiledLayerData::Encoded(_)) => return Err(TiledError::Encoded(name.clone())),
+            None => return Err(TiledError::Infinite),
+        };
+
+        if data.len() != width * height {
+            return Err(TiledError::LayerData {
+                layer: name.clone(),
+                expected: width * height,
+                found: data.len(),
+            });
+        }
+
+        for (i, gid) in data.iter().enumerate() {
+            let gid = gid & GID_MASK;
+            if gid == 0 {
+                continue;
+            }
+
+            let (x, y) = (i % width, i / width);
+
+            let Some(kind) = tile_kind(tiled, gid)? else {
+                unmapped.push(UnmappedTile {
+                    layer: name.clone(),
+                    x,
+                    y,
+                    gid,
+                });
+                continue;
+            };
+
+            let inv_y = height - y - 1;
+
+            map.0[(inv_y, x)] = kind;
+        }
+    }
+
+    for object in objects {
+        if object.class.is_empty() {
+            continue;
+        }
+
+        let pos = object_pos(tiled, object).ok_or(TiledError::ObjectOutOfBounds(object.id))?;
+
+        match object.class.to_lowercase().as_str() {
+            "spawner" => {
+                map.0[(pos.y, pos.x)] = TileKind::Spawn;
+
+                if let Some(value) = property(&object.properties, "index") {
+                    let index = value.as_u64().ok_or_else(|| TiledError::InvalidProperty {
+                        id: object.id,
+                        property: "index",
+                        value: value.to_string(),
+                    })?;
+                    map.2.spawners.insert(pos, index as usize);
+                }
+            }
+            "home" => {
+                let kind = match property(&object.properties, "kind") {
+                    Some(value) => value
+                        .as_str()
+                        .and_then(|s| TileKind::from_str(s).ok())
+                        .ok_or_else(|| TiledError::InvalidProperty {
+                            id: object.id,
+                            property: "kind",
+                            value: value.to_string(),
+                        })?,
+                    None => TileKind::Home,
+                };
+
+                map.0[(pos.y, pos.x)] = kind;
+            }
+            "critter" => {
+                let value =
+                    property(&object.properties, "kind").ok_or(TiledError::MissingProperty {
+                        id: object.id,
+                        property: "kind",
+                    })?;
+                let kind = value
+                    .as_str()
+                    .and_then(|s| CritterKind::from_str(s).ok())
+                    .ok_or_else(|| TiledError::InvalidProperty {
+                        id: object.id,
+                        property: "kind",
+                        value: value.to_string(),
+                    })?;
+
+                map.2.critters.push((pos, kind));
+            }
+            _ => {
+                return Err(TiledError::UnknownObjectClass {
+                    id: object.id,
+                    class: object.class.clone(),
+                })
+            }
+        }
+    }
+
+    Ok((map, unmapped))
+}
+
+type TileLayer<'a> = (&'a String, usize, usize, &'a Option<TiledLayerData>);
+
+/// Collects the tile layers and objects of `layers` and any groups within them, in
+/// drawing order.
+fn flatten_layers<'a>(
+    layers: &'a [TiledLayer],
+    tile_layers: &mut Vec<TileLayer<'a>>,
+    objects: &mut Vec<&'a TiledObject>,
+) {
+    for layer in layers {
+        match layer {
+            TiledLayer::TileLayer {
+                name,
+                width,
+                height,
+                data,
+            } => tile_layers.push((name, *width, *height, data)),
+            TiledLayer::ObjectGroup { objects: o, .. } => objects.extend(o),
+            TiledLayer::Group { layers } => flatten_layers(layers, tile_layers, objects),
+            TiledLayer::Other => {}
+        }
+    }
+}
+
+/// Finds the tile kind given by the `kind` property of a tile in the map's
+/// tilesets.
+fn tile_kind(tiled: &TiledMap, gid: u32) -> Result<Option<TileKind>, TiledError> {
+    let Some(tileset) = tiled
+        .tilesets
+        .iter()
+        .filter(|tileset| tileset.firstgid <= gid)
+        .max_by_key(|tileset| tileset.firstgid)
+    else {
+        return Err(TiledError::MissingTileset(gid));
+    };
+
+    let id = gid - tileset.firstgid;
+
+    let Some(value) = tileset
+        .tileset
+        .tiles
+        .iter()
+        .find(|tile| tile.id == id)
+        .and_then(|tile| property(&tile.properties, "kind"))
+    else {
+        return Ok(None);
+    };
+
+    value
+        .as_str()
+        .and_then(|s| TileKind::from_str(s).ok())
+        .map(Some)
+        .ok_or_else(|| TiledError::UnknownTileKind {
+            gid,
+            value: value.to_string(),
+        })
+}
+
+/// Finds the tile under the center of an object.
+fn object_pos(tiled: &TiledMap, object: &TiledObject) -> Option<TilePos> {
+    let top = if object.gid.is_some() {
+        object.y - object.height
+    } else {
+        object.y
+    };
+
+    let x = ((object.x + object.width / 2.) / tiled.tilewidth).floor();
+    let y = ((top + object.height / 2.) / tiled.tileheight).floor();
+
+    if x < 0. || y < 0. || x as usize >= tiled.width || y as usize >= tiled.height {
+        return None;
+    }
+
+    Some(TilePos {
+        x: x as usize,
+        y: tiled.height - y as usize - 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLIPPED: u32 = 0x8000_0000;
+
+    /// A 3×2 map of 12px tiles. The first tileset maps ids 0 and 1 to stone and
+    /// road, and the second maps id 0 to forest.
+    fn tiled(data: &[u32], objects: &str) -> TiledMap {
+        let data = data
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        serde_json::from_str(&format!(
+            r#"{{
+                "width": 3,
+                "height": 2,
+                "tilewidth": 12,
+                "tileheight": 12,
+                "orientation": "orthogonal",
+                "layers": [
+                    {{ "type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [{data}] }},
+                    {{ "type": "objectgroup", "objects": [{objects}] }}
+                ],
+                "tilesets": [
+                    {{ "firstgid": 1, "tiles": [
+                        {{ "id": 0, "properties": [{{ "name": "kind", "type": "string", "value": "Stone" }}] }},
+                        {{ "id": 1, "properties": [{{ "name": "kind", "type": "string", "value": "Road" }}] }}
+                    ] }},
+                    {{ "firstgid": 10, "tiles": [
+                        {{ "id": 0, "properties": [{{ "name": "kind", "type": "string", "value": "Forest" }}] }}
+                    ] }}
+                ]
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn tiles() {
+        let (map, unmapped) =
+            map_from_tiled(&tiled(&[1, 2, 10, 1 | FLIPPED, 0, 2 | (FLIPPED >> 1)], "")).unwrap();
+
+        assert!(unmapped.is_empty());
+        // Tiled's first row is the top of the map.
+        assert_eq!(map.0[(1, 0)], TileKind::Stone);
+        assert_eq!(map.0[(1, 1)], TileKind::Road);
+        assert_eq!(map.0[(1, 2)], TileKind::Forest);
+        assert_eq!(map.0[(0, 0)], TileKind::Stone);
+        assert_eq!(map.0[(0, 1)], TileKind::Empty);
+        assert_eq!(map.0[(0, 2)], TileKind::Road);
+    }
+
+    #[test]
+    fn tiles_without_kind_are_unmapped() {
+        let (_, unmapped) = map_from_tiled(&tiled(&[1, 3, 1, 1, 1, 1], "")).unwrap();
+
+        assert!(matches!(
+            unmapped.as_slice(),
+            [UnmappedTile {
+                x: 1,
+                y: 0,
+                gid: 3,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn unknown_tile_id() {
+        let mut tiled = tiled(&[1, 1, 1, 1, 1, 1], "");
+        tiled.tilesets.remove(0);
+
+        assert!(matches!(
+            map_from_tiled(&tiled),
+            Err(TiledError::MissingTileset(1))
+        ));
+    }
+
+    #[test]
+    fn objects() {
+        let (map, _) = map_from_tiled(&tiled(
+            &[1; 6],
+            r#"
+                { "id": 1, "type": "spawner", "x": 12, "y": 0, "width": 12, "height": 12,
+                  "properties": [{ "name": "index", "type": "int", "value": 3 }] },
+                { "id": 2, "type": "home", "gid": 1, "x": 24, "y": 24, "width": 12, "height": 12 },
+                { "id": 3, "type": "critter", "x": 5, "y": 20,
+                  "properties": [{ "name": "kind", "type": "string", "value": "Snake" }] }
+            "#,
+        ))
+        .unwrap();
+
+        assert_eq!(map.0[(1, 1)], TileKind::Spawn);
+        assert_eq!(map.2.spawners.get(&TilePos { x: 1, y: 1 }), Some(&3));
+        // Tile objects are positioned by their bottom left corner.
+        assert_eq!(map.0[(0, 2)], TileKind::Home);
+        assert!(matches!(
+            map.2.critters.as_slice(),
+            [(TilePos { x: 0, y: 0 }, CritterKind::Snake)]
+        ));
+    }
+
+    #[test]
+    fn object_outside_of_map() {
+        let result = map_from_tiled(&tiled(
+            &[1; 6],
+            r#"{ "id": 7, "type": "home", "x": 40, "y": 0, "width": 12, "height": 12 }"#,
+        ));
+
+        assert!(matches!(result, Err(TiledError::ObjectOutOfBounds(7))));
+    }
+}
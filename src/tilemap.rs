@@ -1,21 +1,30 @@
+use std::collections::HashMap;
+
 use crate::{
+    critter::CritterKind,
     hit_points::HitPoints,
     home::Home,
     layer,
-    level::{LevelConfig, LevelHandle},
+    level::{
+        LevelConfig, LevelHandle, LevelHandles, LevelReloadedEvent, Tuning,
+        DEFAULT_TOWER_HIT_POINTS,
+    },
     loading::LoadingAssets,
+    map_loader::MapFileLoaderSettings,
+    palette::TileProperties,
     spawner::{Spawner, SpawnerIndex},
     GameState,
 };
 use bevy::prelude::*;
 use grid::Grid;
-use serde::Deserialize;
-use strum_macros::EnumIter;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, EnumString};
 
 pub struct TilemapPlugin;
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<Map>()
+            .add_event::<MapChangedEvent>()
             .register_type::<TileKind>()
             .register_type::<TilePos>()
             .add_systems(Update, queue_load.run_if(in_state(GameState::Loading)))
@@ -28,7 +37,20 @@ impl Plugin for TilemapPlugin {
 pub const SCALE: Vec2 = Vec2::splat(2.);
 pub const TILE_SIZE: Vec2 = Vec2::splat(12.);
 
-#[derive(Reflect, Debug, Component, Clone, Copy, EnumIter, Default)]
+#[derive(
+    Reflect,
+    Debug,
+    Component,
+    Deserialize,
+    Clone,
+    Copy,
+    EnumIter,
+    EnumString,
+    Default,
+    Hash,
+    Eq,
+    PartialEq,
+)]
 pub enum TileKind {
     #[default]
     Empty,
@@ -84,54 +106,6 @@ pub enum TileKind {
     BonesD,
 }
 
-pub struct TileNotMappedError;
-
-impl TryFrom<[u8; 3]> for TileKind {
-    type Error = TileNotMappedError;
-
-    fn try_from(value: [u8; 3]) -> Result<Self, Self::Error> {
-        match value {
-            [0, 0, 0] => Ok(TileKind::Empty),
-            [10, 10, 10] => Ok(Self::Dirt),
-            [255, 255, 255] => Ok(TileKind::Stone),
-            [235, 235, 235] => Ok(TileKind::CrystalHidden),
-            [225, 225, 225] => Ok(TileKind::MetalHidden),
-            [200, 0, 0] => Ok(Self::Mountain),
-            [255, 0, 0] => Ok(Self::Peak),
-            [150, 0, 0] => Ok(Self::Volcano),
-            [0, 255, 0] => Ok(Self::Forest),
-            [0, 200, 0] => Ok(Self::GrassA),
-            [0, 180, 0] => Ok(Self::GrassB),
-            [0, 0, 255] => Ok(Self::River),
-            [255, 0, 255] => Ok(Self::StoneTunnel),
-            [255, 155, 0] => Ok(Self::Home),
-            [255, 156, 0] => Ok(Self::HomeTwo),
-            [255, 255, 0] => Ok(Self::Bridge),
-            [255, 200, 0] => Ok(Self::Road),
-            [0, 255, 255] => Ok(Self::Spawn),
-            [255, 180, 0] => Ok(Self::DirtPathNSA),
-            [255, 180, 10] => Ok(Self::DirtPathNSB),
-            [255, 180, 20] => Ok(Self::DirtPathEWA),
-            [255, 180, 30] => Ok(Self::DirtPathEWB),
-            [255, 180, 40] => Ok(Self::DirtPathSW),
-            [255, 180, 50] => Ok(Self::DirtPathNW),
-            [255, 180, 60] => Ok(Self::DirtPathSE),
-            [255, 180, 70] => Ok(Self::DirtPathNE),
-            [255, 180, 80] => Ok(Self::DirtPathNSW),
-            [255, 180, 90] => Ok(Self::DirtPathSEW),
-            [255, 180, 100] => Ok(Self::DirtPathNSE),
-            [255, 180, 110] => Ok(Self::DirtPathNEW),
-            [255, 180, 120] => Ok(Self::DirtPathNSEW),
-            [100, 100, 50] => Ok(Self::Stump),
-            [255, 100, 0] => Ok(Self::BonesA),
-            [255, 110, 0] => Ok(Self::BonesB),
-            [255, 120, 0] => Ok(Self::BonesC),
-            [255, 130, 0] => Ok(Self::BonesD),
-            _ => Err(TileNotMappedError),
-        }
-    }
-}
-
 impl TileKind {
     pub fn atlas_index(&self) -> usize {
         match self {
@@ -208,10 +182,24 @@ impl TileKind {
     pub fn buildable(&self) -> bool {
         matches!(self, TileKind::Dirt)
     }
+    /// The hit points that tiles of this kind start with, if they can be damaged.
+    pub fn hit_points(&self) -> Option<u32> {
+        match self {
+            TileKind::Home | TileKind::HomeTwo => Some(30),
+            TileKind::Stone => Some(8),
+            TileKind::CrystalHidden | TileKind::MetalHidden => Some(40),
+            TileKind::Tower => Some(DEFAULT_TOWER_HIT_POINTS),
+            _ => None,
+        }
+    }
 }
 
+/// The tiles of a map, along with the tile properties from the [`Palette`] it was
+/// loaded with and any objects placed by the map file.
+///
+/// [`Palette`]: crate::palette::Palette
 #[derive(Component, Asset, TypePath, Clone)]
-pub struct Map(pub Grid<TileKind>);
+pub struct Map(pub Grid<TileKind>, pub TileProperties, pub MapObjects);
 
 impl Map {
     pub fn size_vec2(&self) -> Vec2 {
@@ -238,8 +226,80 @@ impl Map {
     }
 
     pub fn new(height: usize, width: usize) -> Self {
-        Self(Grid::new(height, width))
+        Self(
+            Grid::new(height, width),
+            TileProperties::default(),
+            MapObjects::default(),
+        )
+    }
+
+    pub fn properties(&self) -> &TileProperties {
+        &self.1
+    }
+
+    pub fn objects(&self) -> &MapObjects {
+        &self.2
+    }
+
+    /// Finds every home tile.
+    pub fn homes(&self) -> Vec<TilePos> {
+        (0..self.0.cols())
+            .flat_map(|x| (0..self.0.rows()).map(move |y| TilePos { x, y }))
+            .filter(|pos| matches!(self.0[(pos.y, pos.x)], TileKind::Home | TileKind::HomeTwo))
+            .collect()
     }
+
+    /// Finds every spawner tile along with its [`SpawnerIndex`].
+    ///
+    /// Spawners without an index in [`MapObjects`] are given the lowest unused
+    /// indices, in column order starting from the bottom left.
+    pub fn spawners(&self) -> Vec<(TilePos, usize)> {
+        let explicit = self.2.spawners.values().collect::<Vec<_>>();
+        let mut next_index = 0;
+        let mut spawners = vec![];
+
+        for x in 0..self.0.cols() {
+            for y in 0..self.0.rows() {
+                if self.0[(y, x)] != TileKind::Spawn {
+                    continue;
+                }
+
+                let pos = TilePos { x, y };
+                let index = match self.2.spawners.get(&pos) {
+                    Some(index) => *index,
+                    None => {
+                        while explicit.contains(&&next_index) {
+                            next_index += 1;
+                        }
+                        let index = next_index;
+                        next_index += 1;
+                        index
+                    }
+                };
+
+                spawners.push((pos, index));
+            }
+        }
+
+        spawners
+    }
+}
+
+/// Things that a map file places on the map in addition to its tiles. Only some
+/// map formats can describe these.
+#[derive(Clone, Default, Debug)]
+pub struct MapObjects {
+    /// Spawner tiles with an explicit [`SpawnerIndex`]. Other spawner tiles are
+    /// given the lowest unused indices, in the order they are found.
+    pub spawners: HashMap<TilePos, usize>,
+    /// Critters that are spawned along with the level's own critters.
+    pub critters: Vec<(TilePos, CritterKind)>,
+}
+
+/// Sent when a tile of the [`Map`] changes while a level is being played.
+#[derive(Event)]
+pub struct MapChangedEvent {
+    pub pos: TilePos,
 }
 
 #[derive(Component)]
@@ -248,6 +308,7 @@ pub struct TileEntities(pub Grid<Option<Entity>>);
 #[derive(
     Reflect,
     Component,
+    Serialize,
     Deserialize,
     Debug,
     Clone,
@@ -263,6 +324,11 @@ pub struct TilePos {
     pub x: usize,
     pub y: usize,
 }
+impl std::fmt::Display for TilePos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
 impl From<TilePos> for Vec2 {
     fn from(value: TilePos) -> Self {
         Vec2::new(value.x as f32, value.y as f32)
@@ -324,7 +390,7 @@ fn queue_load(
     asset_server: Res<AssetServer>,
     mut loading_assets: ResMut<LoadingAssets>,
     mut layouts: ResMut<Assets<TextureAtlasLayout>>,
-    level_handle: Option<Res<LevelHandle>>,
+    mut level_handles: ResMut<LevelHandles>,
     levels: Res<Assets<LevelConfig>>,
     mut queued: Local<bool>,
 ) {
@@ -332,15 +398,41 @@ fn queue_load(
         return;
     }
 
-    let Some(level_handle) = level_handle else {
+    if level_handles.levels.is_empty() {
         return;
-    };
+    }
 
-    let Some(level) = levels.get(&level_handle.0) else {
+    let Some(configs) = level_handles
+        .levels
+        .iter()
+        .map(|handle| levels.get(handle))
+        .collect::<Option<Vec<_>>>()
+    else {
         return;
     };
 
-    let tilemap_handle = asset_server.load(&level.map);
+    let maps = configs
+        .iter()
+        .map(|level| {
+            let strict = level.strict_map;
+            let palette = level.palette.clone();
+            asset_server.load_with_settings::<Map, MapFileLoaderSettings>(
+                &level.map,
+                move |settings: &mut MapFileLoaderSettings| {
+                    settings.strict = strict;
+                    settings.palette.clone_from(&palette);
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    for map in &maps {
+        loading_assets.0.push(map.id().into());
+    }
+
+    // The first level is selected by default.
+    commands.insert_resource(TilemapHandle(maps[0].clone()));
+
+    level_handles.maps = maps;
 
     let texture_handle = asset_server.load("urizen_onebit_tileset__v1d0.png");
     loading_assets.0.push(texture_handle.id().into());
@@ -355,9 +447,6 @@ fn queue_load(
     // Workaround for https://github.com/bevyengine/bevy/issues/11219
     atlas.size = UVec2::new(1340, 651);
 
-    loading_assets.0.push(tilemap_handle.id().into());
-
-    commands.insert_resource(TilemapHandle(tilemap_handle));
     commands.insert_resource(AtlasHandle {
         layout: layouts.add(atlas),
         image: texture_handle,
@@ -366,12 +455,32 @@ fn queue_load(
     *queued = true;
 }
 
+/// The tuning of the level being played, if `handle` is that level's map.
+///
+/// Tuning isn't applied by the map loader, because levels that share a map file
+/// share its asset.
+fn level_tuning<'a>(
+    handle: &Handle<Map>,
+    asset_server: &AssetServer,
+    level_handle: Option<&LevelHandle>,
+    levels: &'a Assets<LevelConfig>,
+) -> Option<&'a Tuning> {
+    let level = levels.get(&level_handle?.0)?;
+    let path = asset_server.get_path(handle.id())?;
+
+    (path.path() == std::path::Path::new(&level.map)).then_some(&level.tuning)
+}
+
 pub fn process_loaded_maps(
     mut commands: Commands,
     mut map_events: EventReader<AssetEvent<Map>>,
+    mut reload_events: EventReader<LevelReloadedEvent>,
     maps: Res<Assets<Map>>,
-    mut map_query: Query<(&TilemapHandle, &AtlasHandle, &mut TileEntities)>,
+    mut map_query: Query<(&TilemapHandle, &AtlasHandle, &mut TileEntities, &mut Map)>,
     new_maps: Query<&TilemapHandle, Added<TilemapHandle>>,
+    asset_server: Res<AssetServer>,
+    level_handle: Option<Res<LevelHandle>>,
+    levels: Res<Assets<LevelConfig>>,
 ) {
     let mut changed_maps = Vec::<AssetId<Map>>::default();
     for event in map_events.read() {
@@ -398,75 +507,98 @@ pub fn process_loaded_maps(
         changed_maps.push(new_map_handle.0.id());
     }
 
-    for changed_map in changed_maps.iter() {
-        for (map_handle, atlas_handle, mut tile_entities) in map_query.iter_mut() {
-            if map_handle.0.id() != *changed_map {
-                continue;
-            }
+    // The level's tuning may have changed.
+    let reloaded = !reload_events.is_empty();
+    reload_events.clear();
 
-            for entity in tile_entities.0.iter().flatten() {
-                commands.entity(*entity).despawn();
-            }
+    for (map_handle, atlas_handle, mut tile_entities, mut tiles) in map_query.iter_mut() {
+        let changed = changed_maps.contains(&map_handle.0.id());
+        if !changed && !reloaded {
+            continue;
+        }
 
-            let Some(map) = maps.get(&map_handle.0) else {
-                continue;
-            };
-
-            tile_entities.0.fill(None);
-
-            let mut spawner_index = 0;
-
-            for x in 0..map.0.cols() {
-                for y in 0..map.0.rows() {
-                    let tile = &map.0[(y, x)];
-
-                    let mut command = commands.spawn((
-                        Sprite {
-                            image: atlas_handle.image.clone(),
-                            texture_atlas: Some(TextureAtlas {
-                                layout: atlas_handle.layout.clone(),
-                                index: tile.atlas_index(),
-                            }),
-                            ..default()
-                        },
-                        Transform {
-                            scale: SCALE.extend(1.),
-                            translation: map
-                                .pos_to_world(TilePos { x, y })
-                                .extend(layer::BACKGROUND),
-                            ..default()
-                        },
-                        TilePos { x, y },
-                        *tile,
-                        Name::new("Tile"),
-                    ));
-
-                    match tile {
-                        TileKind::Spawn => {
-                            command.insert((
-                                Spawner,
-                                SpawnerIndex(spawner_index),
-                                Name::new("SpawnerTile"),
-                            ));
-
-                            spawner_index += 1;
-                        }
-                        TileKind::Home | TileKind::HomeTwo => {
-                            command.insert((Home, HitPoints::full(30), Name::new("HomeTile")));
-                        }
-                        TileKind::Stone => {
-                            command.insert((HitPoints::full(8), Name::new("StoneTile")));
-                        }
-                        TileKind::CrystalHidden | TileKind::MetalHidden => {
-                            command.insert((HitPoints::full(40), Name::new("ResourceTile")));
-                        }
-                        _ => {}
-                    }
+        let Some(map) = maps.get(&map_handle.0) else {
+            continue;
+        };
+
+        let mut properties = map.1.clone();
+        if let Some(tuning) = level_tuning(
+            &map_handle.0,
+            &asset_server,
+            level_handle.as_deref(),
+            &levels,
+        ) {
+            tuning.apply(&mut properties);
+        }
 
-                    let entity = command.id();
+        // Tiles that have already been spawned keep their hit points when only the
+        // tuning changes.
+        tiles.1 = properties;
 
-                    tile_entities.0[(y, x)] = Some(entity);
+        if !changed {
+            continue;
+        }
+
+        for entity in tile_entities.0.iter().flatten() {
+            commands.entity(*entity).despawn();
+        }
+
+        tile_entities.0.fill(None);
+
+        let spawners = map.spawners();
+
+        for x in 0..map.0.cols() {
+            for y in 0..map.0.rows() {
+                let tile = &map.0[(y, x)];
+
+                let mut command = commands.spawn((
+                    Sprite {
+                        image: atlas_handle.image.clone(),
+                        texture_atlas: Some(TextureAtlas {
+                            layout: atlas_handle.layout.clone(),
+                            index: tile.atlas_index(),
+                        }),
+                        ..default()
+                    },
+                    Transform {
+                        scale: SCALE.extend(1.),
+                        translation: map.pos_to_world(TilePos { x, y }).extend(layer::BACKGROUND),
+                        ..default()
+                    },
+                    TilePos { x, y },
+                    *tile,
+                    Name::new("Tile"),
+                ));
+
+                match tile {
+                    TileKind::Spawn => {
+                        let index = spawners
+                            .iter()
+                            .find(|(pos, _)| *pos == TilePos { x, y })
+                            .map(|(_, index)| *index)
+                            .unwrap_or_default();
+
+                        command.insert((Spawner, SpawnerIndex(index), Name::new("SpawnerTile")));
+                    }
+                    TileKind::Home | TileKind::HomeTwo => {
+                        command.insert((Home, Name::new("HomeTile")));
+                    }
+                    TileKind::Stone => {
+                        command.insert(Name::new("StoneTile"));
+                    }
+                    TileKind::CrystalHidden | TileKind::MetalHidden => {
+                        command.insert(Name::new("ResourceTile"));
+                    }
+                    _ => {}
                 }
+
+                if let Some(hit_points) = tiles.properties().hit_points(*tile) {
+                    command.insert(HitPoints::full(hit_points));
+                }
+
+                let entity = command.id();
+
+                tile_entities.0[(y, x)] = Some(entity);
             }
         }
     }
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
+    damage::DamageType,
     radio_button::{RadioButton, RadioButtonGroup, RadioButtonGroupRelation},
     tilemap::{AtlasHandle, TileKind, SCALE, TILE_SIZE},
     ui::{slice_image_mode, UiAssets, BUTTON_TEXT},
@@ -31,28 +32,39 @@ struct ToolPortrait;
 pub enum Tool {
     #[default]
     Dig,
-    BuildTower,
+    /// Builds a tower that fires the given type of damage.
+    BuildTower(DamageType),
     Dance,
 }
 impl Tool {
     pub fn atlas_index(&self) -> usize {
         match self {
             Self::Dig => 103 * 31 + 1,
-            Self::BuildTower => TileKind::Tower.atlas_index(),
+            Self::BuildTower(_) => TileKind::Tower.atlas_index(),
             Self::Dance => 103 * 31 + 17,
         }
     }
+    pub fn color(&self) -> Color {
+        match self {
+            Self::BuildTower(damage_type) => damage_type.color(),
+            Self::Dig | Self::Dance => Color::WHITE,
+        }
+    }
     pub fn index(&self) -> usize {
         match self {
             Self::Dig => 1,
-            Self::BuildTower => 2,
-            Self::Dance => 3,
+            Self::BuildTower(DamageType::Physical) => 2,
+            Self::BuildTower(DamageType::Fire) => 3,
+            Self::BuildTower(DamageType::Crystal) => 4,
+            Self::Dance => 5,
         }
     }
     pub fn from_index(val: usize) -> Self {
         match val {
             1 => Tool::Dig,
-            2 => Tool::BuildTower,
+            2 => Tool::BuildTower(DamageType::Physical),
+            3 => Tool::BuildTower(DamageType::Fire),
+            4 => Tool::BuildTower(DamageType::Crystal),
             _ => Tool::Dance,
         }
     }
@@ -79,7 +91,7 @@ fn init(mut commands: Commands, ui_assets: Res<UiAssets>, atlas_handle: Res<Atla
             ToolContainer,
         ))
         .with_children(|parent| {
-            for i in 1..3 {
+            for i in 1..5 {
                 let kind = Tool::from_index(i);
 
                 let mut button_command = parent.spawn((
@@ -115,6 +127,7 @@ fn init(mut commands: Commands, ui_assets: Res<UiAssets>, atlas_handle: Res<Atla
                                 layout: atlas_handle.layout.clone(),
                                 index: kind.atlas_index(),
                             }),
+                            color: kind.color(),
                             ..default()
                         },
                         ToolPortrait,
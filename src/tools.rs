@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+
+use image::ImageError;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use thiserror::Error;
+
+use crate::{
+    enemy_table::{EnemyTable, ENEMY_TABLE},
+    level::{LevelCatalog, LevelConfig, LEVEL_CATALOG},
+    map_loader::{map_from_image, map_to_image, MissingColorError},
+    map_text::{map_from_text, map_to_text, MapTextError},
+    mapgen::{generate, MapGenError, MapGenParams},
+    palette::{Palette, DEFAULT_PALETTE},
+    tiled::{map_from_tiled, TiledError, TiledMap, UnmappedTile},
+    tilemap::Map,
+    validation::validate_level,
+};
+
+pub const ASSETS_DIR: &str = "assets";
+
+const USAGE: &str = "\
+Usage:
+    entytd convert-map <input> <output> [--palette <path>]
+        Converts a map between the .map.png and .map.txt formats. Tiled .tmj
+        maps can also be converted, but their spawner indices and critters are
+        lost.
+    entytd validate-levels [<level>...]
+        Checks levels for problems, such as enemies that can't reach a home.
+        Checks every level in the level catalog by default.
+    entytd generate-map <output> [--seed <seed>] [--params <path>]
+        Generates a random map. The same seed and parameters always generate
+        the same map. Parameters are read from a RON file.";
+
+/// Possible errors that can be produced by the command-line tools.
+#[derive(Debug, Error)]
+pub enum ToolError {
+    #[error("{USAGE}")]
+    Usage,
+    #[error("{0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("{0}: {1}")]
+    Image(PathBuf, ImageError),
+    #[error("{0}: {1}")]
+    Palette(PathBuf, ron::error::SpannedError),
+    #[error("{0}: {1}")]
+    Text(PathBuf, MapTextError),
+    #[error("{0}: {1}")]
+    Json(PathBuf, serde_json::Error),
+    #[error("{0}: {1}")]
+    Tiled(PathBuf, TiledError),
+    #[error("{0}: {1}")]
+    Level(PathBuf, ron::error::SpannedError),
+    #[error("{0}: {1}")]
+    EnemyTable(PathBuf, ron::error::SpannedError),
+    #[error("{0}: {1}")]
+    Catalog(PathBuf, ron::error::SpannedError),
+    #[error("{0}: {1}")]
+    MapGenParams(PathBuf, ron::error::SpannedError),
+    #[error("Seed {0}: {1}")]
+    MapGen(u64, MapGenError),
+    #[error("{0}: {1}")]
+    Serialize(PathBuf, ron::Error),
+    #[error("{0}: {1}")]
+    MissingColor(PathBuf, MissingColorError),
+    #[error("{0}: {1} tile(s) could not be mapped")]
+    Unmapped(PathBuf, usize),
+    #[error("{0}: expected a .map.png or .map.txt file")]
+    UnknownFormat(PathBuf),
+    #[error("{0} level(s) failed validation")]
+    InvalidLevels(usize),
+}
+
+/// Runs the command-line tool named by `args`, if any.
+///
+/// Returns `None` if no tool was requested and the game should start normally,
+/// or the process exit code otherwise. Unrecognized arguments, such as those
+/// added by some launchers, don't stop the game from starting.
+pub fn run(mut args: impl Iterator<Item = String>) -> Option<i32> {
+    let command = args.next()?;
+
+    let result = match command.as_str() {
+        "convert-map" => convert_map(args),
+        "validate-levels" => validate_levels(args),
+        "generate-map" => generate_map(args),
+        _ => return None,
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{e}");
+            Some(1)
+        }
+    }
+}
+
+fn convert_map(mut args: impl Iterator<Item = String>) -> Result<(), ToolError> {
+    let (Some(input), Some(output)) = (args.next(), args.next()) else {
+        return Err(ToolError::Usage);
+    };
+
+    let palette = match (args.next().as_deref(), args.next()) {
+        (None, _) => Path::new(ASSETS_DIR).join(DEFAULT_PALETTE),
+        (Some("--palette"), Some(path)) => PathBuf::from(path),
+        _ => return Err(ToolError::Usage),
+    };
+
+    let palette = read_palette(&palette)?;
+    let map = read_map(Path::new(&input), &palette)?;
+    write_map(Path::new(&output), &map, &palette)?;
+
+    println!("Converted {input} to {output}");
+
+    Ok(())
+}
+
+fn validate_levels(args: impl Iterator<Item = String>) -> Result<(), ToolError> {
+    let mut paths = args.map(PathBuf::from).collect::<Vec<_>>();
+
+    if paths.is_empty() {
+        let path = Path::new(ASSETS_DIR).join(LEVEL_CATALOG);
+        let bytes = std::fs::read(&path).map_err(|e| ToolError::Io(path.clone(), e))?;
+        let catalog: LevelCatalog =
+            ron::de::from_bytes(&bytes).map_err(|e| ToolError::Catalog(path.clone(), e))?;
+
+        paths.extend(
+            catalog
+                .levels
+                .iter()
+                .map(|info| Path::new(ASSETS_DIR).join(&info.path)),
+        );
+    }
+
+    let enemies = read_enemy_table(&Path::new(ASSETS_DIR).join(ENEMY_TABLE))?;
+
+    let mut failed = 0;
+
+    for path in &paths {
+        let (errors, warnings) = match validate_level_file(path, &enemies) {
+            Ok(counts) => counts,
+            Err(e) => {
+                eprintln!("{e}");
+                (1, 0)
+            }
+        };
+
+        if errors > 0 {
+            failed += 1;
+        }
+
+        println!(
+            "{}: {} error(s), {} warning(s)",
+            path.display(),
+            errors,
+            warnings
+        );
+    }
+
+    if failed > 0 {
+        return Err(ToolError::InvalidLevels(failed));
+    }
+
+    Ok(())
+}
+
+/// Validates a single level, printing its issues and returning the number of
+/// errors and warnings.
+fn validate_level_file(path: &Path, enemies: &EnemyTable) -> Result<(usize, usize), ToolError> {
+    let bytes = std::fs::read(path).map_err(|e| ToolError::Io(path.into(), e))?;
+    let level: LevelConfig =
+        ron::de::from_bytes(&bytes).map_err(|e| ToolError::Level(path.into(), e))?;
+
+    let assets = Path::new(ASSETS_DIR);
+    let palette = read_palette(&assets.join(level.palette.as_deref().unwrap_or(DEFAULT_PALETTE)))?;
+    let (map, unmapped) = read_map_lenient(&assets.join(&level.map), &palette)?;
+
+    let issues = validate_level(&level, &map, enemies);
+    let mut errors = issues.iter().filter(|issue| issue.is_error()).count();
+    let mut warnings = issues.len() - errors;
+
+    // Unmapped tiles only prevent the map from loading in strict mode.
+    if unmapped > 0 {
+        let severity = if level.strict_map {
+            errors += 1;
+            "error"
+        } else {
+            warnings += 1;
+            "warning"
+        };
+        eprintln!(
+            "{}: {}: Map has {} tile(s) that could not be mapped",
+            path.display(),
+            severity,
+            unmapped
+        );
+    }
+
+    for issue in &issues {
+        let severity = if issue.is_error() { "error" } else { "warning" };
+        eprintln!("{}: {}: {}", path.display(), severity, issue);
+    }
+
+    Ok((errors, warnings))
+}
+
+fn generate_map(mut args: impl Iterator<Item = String>) -> Result<(), ToolError> {
+    let Some(output) = args.next() else {
+        return Err(ToolError::Usage);
+    };
+
+    let mut seed = None;
+    let mut params = MapGenParams::default();
+
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--seed", Some(value)) => seed = Some(value.parse().map_err(|_| ToolError::Usage)?),
+            ("--params", Some(path)) => {
+                let path = PathBuf::from(path);
+                let bytes = std::fs::read(&path).map_err(|e| ToolError::Io(path.clone(), e))?;
+                params = ron::de::from_bytes(&bytes)
+                    .map_err(|e| ToolError::MapGenParams(path.clone(), e))?;
+            }
+            _ => return Err(ToolError::Usage),
+        }
+    }
+
+    let seed = seed.unwrap_or_else(|| SmallRng::from_entropy().gen());
+
+    let palette = read_palette(&Path::new(ASSETS_DIR).join(DEFAULT_PALETTE))?;
+    let map = generate(seed, &params).map_err(|e| ToolError::MapGen(seed, e))?;
+    write_map(Path::new(&output), &map, &palette)?;
+
+    println!("Generated {output} with seed {seed}");
+
+    Ok(())
+}
+
+pub fn read_palette(path: &Path) -> Result<Palette, ToolError> {
+    let bytes = std::fs::read(path).map_err(|e| ToolError::Io(path.into(), e))?;
+    ron::de::from_bytes(&bytes).map_err(|e| ToolError::Palette(path.into(), e))
+}
+
+pub fn read_enemy_table(path: &Path) -> Result<EnemyTable, ToolError> {
+    let bytes = std::fs::read(path).map_err(|e| ToolError::Io(path.into(), e))?;
+    ron::de::from_bytes(&bytes).map_err(|e| ToolError::EnemyTable(path.into(), e))
+}
+
+/// Reads a map in any format, failing if any of its tiles can't be mapped.
+pub fn read_map(path: &Path, palette: &Palette) -> Result<Map, ToolError> {
+    let (map, unmapped) = read_map_lenient(path, palette)?;
+
+    if unmapped > 0 {
+        return Err(ToolError::Unmapped(path.into(), unmapped));
+    }
+
+    Ok(map)
+}
+
+/// Reads a map in any format, printing any tiles that can't be mapped and
+/// returning how many there were. Those tiles are left empty.
+pub fn read_map_lenient(path: &Path, palette: &Palette) -> Result<(Map, usize), ToolError> {
+    let name = path.to_string_lossy();
+
+    let (map, unmapped) = if name.ends_with(".map.png") {
+        let img = image::open(path).map_err(|e| ToolError::Image(path.into(), e))?;
+        let (map, unmapped) = map_from_image(&img, palette);
+        for pixel in &unmapped {
+            eprintln!("{}: unmapped color at {}", path.display(), pixel);
+        }
+        (map, unmapped.len())
+    } else if name.ends_with(".map.txt") {
+        let text = std::fs::read_to_string(path).map_err(|e| ToolError::Io(path.into(), e))?;
+        let (mut map, unmapped) =
+            map_from_text(&text).map_err(|e| ToolError::Text(path.into(), e))?;
+        map.1 = palette.tiles.clone();
+        for character in &unmapped {
+            eprintln!("{}: unmapped character at {}", path.display(), character);
+        }
+        (map, unmapped.len())
+    } else if name.ends_with(".tmj") {
+        let (mut map, unmapped) = read_tiled_map(path)?;
+        map.1 = palette.tiles.clone();
+        for tile in &unmapped {
+            eprintln!("{}: tile without a tile kind at {}", path.display(), tile);
+        }
+        (map, unmapped.len())
+    } else {
+        return Err(ToolError::UnknownFormat(path.into()));
+    };
+
+    Ok((map, unmapped))
+}
+
+fn read_tiled_map(path: &Path) -> Result<(Map, Vec<UnmappedTile>), ToolError> {
+    let bytes = std::fs::read(path).map_err(|e| ToolError::Io(path.into(), e))?;
+    let mut tiled: TiledMap =
+        serde_json::from_slice(&bytes).map_err(|e| ToolError::Json(path.into(), e))?;
+
+    // External tileset paths are relative to the map.
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for tileset in &mut tiled.tilesets {
+        let Some(source) = &tileset.source else {
+            continue;
+        };
+
+        let source = dir.join(source);
+        let bytes = std::fs::read(&source).map_err(|e| ToolError::Io(source.clone(), e))?;
+        tileset.tileset =
+            serde_json::from_slice(&bytes).map_err(|e| ToolError::Json(source.clone(), e))?;
+    }
+
+    map_from_tiled(&tiled).map_err(|e| ToolError::Tiled(path.into(), e))
+}
+
+pub fn write_map(path: &Path, map: &Map, palette: &Palette) -> Result<(), ToolError> {
+    let name = path.to_string_lossy();
+
+    if name.ends_with(".map.png") {
+        let img =
+            map_to_image(map, palette).map_err(|e| ToolError::MissingColor(path.into(), e))?;
+        img.save(path).map_err(|e| ToolError::Image(path.into(), e))
+    } else if name.ends_with(".map.txt") {
+        std::fs::write(path, map_to_text(map)).map_err(|e| ToolError::Io(path.into(), e))
+    } else {
+        Err(ToolError::UnknownFormat(path.into()))
+    }
+}
@@ -1,25 +1,44 @@
 use bevy::prelude::*;
 
 use crate::{
-    designate_tool::Designations,
+    damage::{apply_damage, DamageEvent, DamageType, DamagedEvent},
+    designate_tool::{spawn_marker, Designation, DesignationKind, Designations},
     enemy::EnemyKind,
     hit_points::HitPoints,
     layer,
     movement::Speed,
     particle::ParticleKind,
     settings::ParticlesSetting,
-    tilemap::{AtlasHandle, Map, TileEntities, TileKind, TilePos, SCALE, TILE_SIZE},
+    stone::StoneHealth,
+    tilemap::{
+        AtlasHandle, Map, MapChangedEvent, TileEntities, TileKind, TilePos, SCALE, TILE_SIZE,
+    },
     util::cleanup,
     GameState,
 };
 
+/// Crumbling versions of the tower sprite. The tile stays a [`TileKind::Tower`]
+/// on the map while it's damaged.
+const TOWER_HURT_SPRITE: usize = 103 * 22 + 28;
+const TOWER_DYING_SPRITE: usize = 103 * 22 + 29;
+
 pub struct TowerPlugin;
 impl Plugin for TowerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<BuildTowerEvent>()
             .add_systems(
                 Update,
-                (build_tower, attack, bullet_movement).run_if(in_state(GameState::Playing)),
+                (
+                    build_tower,
+                    attack,
+                    bullet_movement.before(apply_damage),
+                    show_hits.after(apply_damage),
+                    show_damage,
+                    designate_repairs,
+                    finish_repairs,
+                    destroy.after(show_hits),
+                )
+                    .run_if(in_state(GameState::Playing)),
             )
             .add_systems(
                 OnExit(GameState::GameOver),
@@ -29,7 +48,15 @@ impl Plugin for TowerPlugin {
 }
 
 #[derive(Component)]
-#[require(TileKind, CooldownTimer, Upgrades, TilePos, Range)]
+#[require(
+    TileKind,
+    CooldownTimer,
+    Upgrades,
+    TilePos,
+    Range,
+    HitPoints,
+    DamageType
+)]
 pub struct Tower;
 
 #[derive(Component, Default)]
@@ -55,20 +82,31 @@ impl Default for Range {
 #[require(Speed, Sprite)]
 struct Bullet {
     damage: u32,
+    kind: DamageType,
     target: Entity,
 }
 
+/// Asks for a finished tower firing the given type of damage at a position.
 #[derive(Event, Debug)]
-pub struct BuildTowerEvent(pub TilePos);
+pub struct BuildTowerEvent(pub TilePos, pub DamageType);
 
 fn attack(
     mut commands: Commands,
-    mut query: Query<(&Transform, &Range, &Upgrades, &mut CooldownTimer), With<Tower>>,
+    mut query: Query<
+        (
+            &Transform,
+            &Range,
+            &Upgrades,
+            &DamageType,
+            &mut CooldownTimer,
+        ),
+        With<Tower>,
+    >,
     time: Res<Time>,
     enemies: Query<(Entity, &Transform), With<EnemyKind>>,
     atlas_handle: Res<AtlasHandle>,
 ) {
-    for (transform, range, upgrades, mut timer) in &mut query {
+    for (transform, range, upgrades, damage_type, mut timer) in &mut query {
         timer.0.tick(time.delta());
         if !timer.0.finished() {
             continue;
@@ -86,6 +124,7 @@ fn attack(
             commands.spawn((
                 Bullet {
                     damage: 1 + upgrades.0,
+                    kind: *damage_type,
                     target: entity,
                 },
                 Speed(4.),
@@ -95,6 +134,7 @@ fn attack(
                         layout: atlas_handle.layout.clone(),
                         index: 103 * 49 + 52,
                     }),
+                    color: damage_type.color(),
                     ..default()
                 },
                 Transform {
@@ -117,6 +157,7 @@ fn build_tower(
     mut designations: ResMut<Designations>,
     mut tilemap_query: Query<(&mut Map, &mut TileEntities)>,
     atlas_handle: Res<AtlasHandle>,
+    mut changed_writer: EventWriter<MapChangedEvent>,
 ) {
     for event in events.read() {
         let Ok((mut tilemap, mut tile_entities)) = tilemap_query.single_mut() else {
@@ -125,14 +166,23 @@ fn build_tower(
 
         let world = tilemap.pos_to_world(event.0).extend(layer::BACKGROUND);
 
-        let Some(tile_kind) = tilemap.0.get_mut(event.0.y, event.0.x) else {
+        let Some(kind) = tilemap.0.get(event.0.y, event.0.x) else {
             continue;
         };
 
-        if !tile_kind.buildable() {
+        if !tilemap.properties().buildable(*kind) {
             continue;
         }
 
+        let hit_points = tilemap
+            .properties()
+            .hit_points(TileKind::Tower)
+            .unwrap_or(1);
+
+        let Some(tile_kind) = tilemap.0.get_mut(event.0.y, event.0.x) else {
+            continue;
+        };
+
         let Some(maybe_tile_entity) = tile_entities.0.get_mut(event.0.y, event.0.x) else {
             continue;
         };
@@ -152,9 +202,12 @@ fn build_tower(
                         layout: atlas_handle.layout.clone(),
                         index: TileKind::Tower.atlas_index(),
                     }),
+                    color: event.1.color(),
                     ..default()
                 },
                 event.0,
+                HitPoints::full(hit_points),
+                event.1,
                 Transform {
                     scale: SCALE.extend(1.),
                     translation: world,
@@ -165,6 +218,7 @@ fn build_tower(
 
         *maybe_tile_entity = Some(id);
         *tile_kind = TileKind::Tower;
+        changed_writer.write(MapChangedEvent { pos: event.0 });
 
         if let Some(designation) = designations.0.remove(&event.0) {
             commands.entity(designation.indicator).despawn();
@@ -175,12 +229,12 @@ fn build_tower(
 fn bullet_movement(
     mut commands: Commands,
     mut query: Query<(Entity, &Bullet, &Speed, &mut Transform)>,
-    mut enemy_query: Query<(&mut HitPoints, &Transform, &EnemyKind), Without<Bullet>>,
+    enemy_query: Query<&Transform, (With<EnemyKind>, Without<Bullet>)>,
     time: Res<Time>,
-    particle_settings: Res<ParticlesSetting>,
+    mut damage_writer: EventWriter<DamageEvent>,
 ) {
     for (bullet_entity, bullet, speed, mut transform) in query.iter_mut() {
-        let Ok((mut hp, enemy, enemy_kind)) = enemy_query.get_mut(bullet.target) else {
+        let Ok(enemy) = enemy_query.get(bullet.target) else {
             commands.entity(bullet_entity).despawn();
             continue;
         };
@@ -194,35 +248,175 @@ fn bullet_movement(
             transform.translation.x += step * dir.x;
             transform.translation.y += step * dir.y;
         } else {
-            hp.sub(bullet.damage);
-
             // TODO sound
-            let amt = if hp.is_zero() {
-                particle_settings.kill_amt() / 2
-            } else {
-                particle_settings.hit_amt() / 2
-            };
-            for _ in 0..amt {
-                commands.spawn((
-                    match enemy_kind {
-                        EnemyKind::Ent | EnemyKind::EntTwo | EnemyKind::EntThree => {
-                            ParticleKind::Wood
-                        }
-                        EnemyKind::EntFour => ParticleKind::Purple,
-                        EnemyKind::Skeleton
-                        | EnemyKind::SkeletonTwo
-                        | EnemyKind::SkeletonThree
-                        | EnemyKind::SkeletonFour => ParticleKind::Bone,
-                    },
-                    Transform::from_translation(enemy.translation),
-                ));
-                commands.spawn((
-                    ParticleKind::Stone,
-                    Transform::from_translation(enemy.translation),
-                ));
-            }
+            damage_writer.write(DamageEvent {
+                target: bullet.target,
+                amount: bullet.damage,
+                kind: bullet.kind,
+            });
 
             commands.entity(bullet_entity).despawn();
         }
     }
 }
+
+fn show_hits(
+    mut commands: Commands,
+    mut events: EventReader<DamagedEvent>,
+    query: Query<&Transform, With<Tower>>,
+    particle_settings: Res<ParticlesSetting>,
+) {
+    for event in events.read() {
+        let Ok(transform) = query.get(event.target) else {
+            continue;
+        };
+
+        // Destroyed towers burst into particles when they're turned into dirt.
+        if event.killed {
+            continue;
+        }
+
+        for _ in 0..particle_settings.hit_amt() {
+            commands.spawn((
+                ParticleKind::Stone,
+                Transform::from_translation(transform.translation),
+            ));
+        }
+    }
+}
+
+/// Shows towers crumbling as they are damaged.
+fn show_damage(mut query: Query<(&HitPoints, &mut Sprite), (With<Tower>, Changed<HitPoints>)>) {
+    for (hp, mut sprite) in &mut query {
+        let index = match StoneHealth::from(hp) {
+            StoneHealth::Full => TileKind::Tower.atlas_index(),
+            StoneHealth::Hurt => TOWER_HURT_SPRITE,
+            StoneHealth::Dying | StoneHealth::Dead => TOWER_DYING_SPRITE,
+        };
+
+        if let Some(ref mut atlas) = sprite.texture_atlas {
+            atlas.index = index;
+        }
+    }
+}
+
+/// Asks workers to repair towers as soon as they are damaged.
+fn designate_repairs(
+    mut commands: Commands,
+    query: Query<(&HitPoints, &TilePos), (With<Tower>, Changed<HitPoints>)>,
+    mut designations: ResMut<Designations>,
+    tilemap_query: Query<&Map>,
+    atlas_handle: Res<AtlasHandle>,
+) {
+    let Ok(map) = tilemap_query.single() else {
+        return;
+    };
+
+    for (hp, pos) in &query {
+        if hp.is_zero() || hp.is_full() || designations.0.contains_key(pos) {
+            continue;
+        }
+
+        let indicator = spawn_marker(
+            &mut commands,
+            &atlas_handle,
+            DesignationKind::Repair,
+            map.pos_to_world(*pos),
+        );
+
+        designations.0.insert(
+            *pos,
+            Designation {
+                kind: DesignationKind::Repair,
+                indicator,
+                workers: 0,
+            },
+        );
+    }
+}
+
+fn finish_repairs(
+    mut commands: Commands,
+    mut designations: ResMut<Designations>,
+    query: Query<&HitPoints, With<Tower>>,
+    tilemap_query: Query<&TileEntities>,
+) {
+    let Ok(tile_entities) = tilemap_query.single() else {
+        return;
+    };
+
+    designations.0.retain(|pos, designation| {
+        if !matches!(designation.kind, DesignationKind::Repair) {
+            return true;
+        }
+
+        let damaged = tile_entities.0[(pos.y, pos.x)]
+            .and_then(|entity| query.get(entity).ok())
+            .is_some_and(|hp| !hp.is_full());
+
+        if !damaged {
+            commands.entity(designation.indicator).despawn();
+        }
+
+        damaged
+    });
+}
+
+/// Turns destroyed towers back into dirt.
+fn destroy(
+    mut commands: Commands,
+    query: Query<(Entity, &HitPoints, &TilePos, &Transform), With<Tower>>,
+    mut tilemap_query: Query<(&mut Map, &mut TileEntities)>,
+    mut designations: ResMut<Designations>,
+    mut changed_writer: EventWriter<MapChangedEvent>,
+    atlas_handle: Res<AtlasHandle>,
+    particle_settings: Res<ParticlesSetting>,
+) {
+    for (entity, hp, pos, transform) in &query {
+        if !hp.is_zero() {
+            continue;
+        }
+
+        let Ok((mut map, mut tile_entities)) = tilemap_query.single_mut() else {
+            return;
+        };
+
+        commands.entity(entity).despawn();
+
+        for _ in 0..particle_settings.kill_amt() {
+            commands.spawn((
+                ParticleKind::Stone,
+                Transform::from_translation(transform.translation),
+            ));
+        }
+
+        let tile = commands
+            .spawn((
+                Sprite {
+                    image: atlas_handle.image.clone(),
+                    texture_atlas: Some(TextureAtlas {
+                        layout: atlas_handle.layout.clone(),
+                        index: TileKind::Dirt.atlas_index(),
+                    }),
+                    ..default()
+                },
+                Transform {
+                    scale: SCALE.extend(1.),
+                    translation: map.pos_to_world(*pos).extend(layer::BACKGROUND),
+                    ..default()
+                },
+                *pos,
+                TileKind::Dirt,
+                Name::new("Tile"),
+            ))
+            .id();
+
+        tile_entities.0[(pos.y, pos.x)] = Some(tile);
+        map.0[(pos.y, pos.x)] = TileKind::Dirt;
+        changed_writer.write(MapChangedEvent { pos: *pos });
+
+        if let Some(designation) = designations.0.remove(pos) {
+            commands.entity(designation.indicator).despawn();
+        }
+    }
+}
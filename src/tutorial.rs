@@ -2,6 +2,7 @@ use bevy::{audio::Volume, prelude::*};
 
 use crate::{
     currency::Currency,
+    damage::DamageType,
     designate_tool::{DesignationKind, Designations},
     settings::{SfxSetting, TutorialFinishedSetting},
     sound::SoundAssets,
@@ -156,7 +157,7 @@ pub fn dug_more(
     sound_assets: Res<SoundAssets>,
 ) {
     if matches!(*tutorial_state, TutorialState::DigMore)
-        && currency.has(&DesignationKind::BuildTower.price())
+        && currency.has(&DesignationKind::BuildTower(DamageType::Physical).price())
     {
         *tutorial_state = TutorialState::Build;
 
@@ -171,7 +172,7 @@ pub fn built(designations: Res<Designations>, mut tutorial_state: ResMut<Tutoria
     let built = designations
         .0
         .iter()
-        .any(|(_, v)| matches!(v.kind, DesignationKind::BuildTower));
+        .any(|(_, v)| matches!(v.kind, DesignationKind::BuildTower(_)));
     if built {
         *tutorial_state = TutorialState::Done;
     }
@@ -206,7 +207,7 @@ fn update(
             }
             TutorialState::Build => {
                 text.0 =
-                    "Use the build tool (2) to select a nice spot next to the main road to build a tower."
+                    "Use the build tool (2) to select a nice spot next to the main road to build a tower.\n\nFire towers (3) cost more metal and burn ents. Crystal towers (4) cost crystal and cut through skeletons."
                         .to_string();
             }
             _ => {}
@@ -0,0 +1,509 @@
+use std::collections::HashSet;
+
+use pathfinding::prelude::{bfs_reach, strongly_connected_components};
+use thiserror::Error;
+
+use crate::{
+    critter::CritterKind,
+    enemy::EnemyKind,
+    enemy_table::EnemyTable,
+    level::LevelConfig,
+    objective::Objective,
+    pathfinding::{critter_cost_fn, enemy_cost_fn, NeighborCostIter},
+    spawner::SpawnTrigger,
+    tilemap::{Map, TileKind, TilePos},
+    waves::Wave,
+};
+
+/// A problem with a level that would make it unplayable or behave unexpectedly.
+///
+/// Tile coordinates have their origin at the bottom left, as in level files.
+#[derive(Debug, Error)]
+pub enum LevelIssue {
+    #[error("Map has no home tiles")]
+    NoHomes,
+    #[error("Level has no waves")]
+    NoWaves,
+    #[error("Spawners at {first} and {second} both have index {index}")]
+    DuplicateSpawner {
+        index: usize,
+        first: TilePos,
+        second: TilePos,
+    },
+    #[error("Spawner {index} at {pos} is not used by any wave")]
+    UnusedSpawner { index: usize, pos: TilePos },
+    #[error("Wave {wave}: no spawn is in the group {group:?} that another spawn waits for")]
+    MissingGroup { wave: usize, group: String },
+    #[error("Wave {wave}: a spawn in the group {group:?} waits for its own group to finish")]
+    GroupWaitsForItself { wave: usize, group: String },
+    #[error("Wave {wave}: the groups {groups:?} wait for each other to finish")]
+    GroupCycle { wave: usize, groups: Vec<String> },
+    #[error(
+        "Wave {wave}: spawner {spawner} waits for {kills} kills, but only {available} enemies can be defeated before it"
+    )]
+    UnreachableKills {
+        wave: usize,
+        spawner: usize,
+        kills: usize,
+        available: usize,
+    },
+    #[error("Wave {wave}: spawner {spawner} does not exist")]
+    MissingSpawner { wave: usize, spawner: usize },
+    #[error(
+        "Wave {wave}: {kind:?} from spawner {spawner} at {from} can't reach {target} next to the home at {home}"
+    )]
+    UnreachableHome {
+        wave: usize,
+        spawner: usize,
+        kind: EnemyKind,
+        from: TilePos,
+        target: TilePos,
+        home: TilePos,
+    },
+    #[error("The enemy table has no stats for {0:?}")]
+    MissingEnemyStats(EnemyKind),
+    #[error("Every required objective only has to be kept, so the level can't be won")]
+    OnlyKeptObjectives,
+    #[error("Star thresholds {0:?} are not in increasing order")]
+    StarThresholdsOutOfOrder([u32; 3]),
+    #[error("Worker spawn {0} is outside of the map")]
+    WorkerSpawnOutOfBounds(TilePos),
+    #[error("{kind:?} critter at {pos} is outside of the map")]
+    CritterOutOfBounds { kind: CritterKind, pos: TilePos },
+    #[error("{kind:?} critter at {pos} is on {tile:?}, which it can't walk on")]
+    CritterTile {
+        kind: CritterKind,
+        pos: TilePos,
+        tile: TileKind,
+    },
+}
+impl LevelIssue {
+    /// Whether the issue makes the level broken, rather than just suspicious.
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self,
+            Self::NoWaves
+                | Self::UnusedSpawner { .. }
+                | Self::MissingEnemyStats(_)
+                | Self::StarThresholdsOutOfOrder(_)
+        )
+    }
+}
+
+/// Checks that a level's waves and critters make sense for its map, and that
+/// every enemy can reach every [`Home`] from the spawner it starts at.
+///
+/// [`Home`]: crate::home::Home
+pub fn validate_level(level: &LevelConfig, map: &Map, enemies: &EnemyTable) -> Vec<LevelIssue> {
+    let mut issues = vec![];
+
+    let homes = map.homes();
+    if homes.is_empty() {
+        issues.push(LevelIssue::NoHomes);
+    }
+
+    if let Some(pos) = level.worker_spawn {
+        if map.0.get(pos.y, pos.x).is_none() {
+            issues.push(LevelIssue::WorkerSpawnOutOfBounds(pos));
+        }
+    }
+
+    if level.waves.is_empty() {
+        issues.push(LevelIssue::NoWaves);
+    }
+
+    if !level.objectives.is_empty() && level.objectives.iter().all(Objective::is_kept) {
+        issues.push(LevelIssue::OnlyKeptObjectives);
+    }
+
+    if let Some(stars) = level.scoring.stars {
+        if !stars.is_sorted() {
+            issues.push(LevelIssue::StarThresholdsOutOfOrder(stars));
+        }
+    }
+
+    let spawners = map.spawners();
+    for (i, (pos, index)) in spawners.iter().enumerate() {
+        if let Some((first, _)) = spawners[..i].iter().find(|(_, other)| other == index) {
+            issues.push(LevelIssue::DuplicateSpawner {
+                index: *index,
+                first: *first,
+                second: *pos,
+            });
+        }
+
+        let used = level
+            .waves
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .any(|spawn| spawn.spawner == *index);
+        if !used {
+            issues.push(LevelIssue::UnusedSpawner {
+                index: *index,
+                pos: *pos,
+            });
+        }
+    }
+
+    // The same spawner and enemy kind are often used in many waves, but only
+    // need to be checked once.
+    let mut checked = vec![];
+    let mut missing_stats = vec![];
+
+    for (wave_index, wave) in level.waves.iter().enumerate() {
+        let wave_number = wave_index + 1;
+
+        check_triggers(wave_number, wave, &mut issues);
+
+        for spawn in &wave.spawns {
+            if let SpawnTrigger::GroupFinished(group) = &spawn.trigger {
+                let exists = wave
+                    .spawns
+                    .iter()
+                    .any(|other| other.group.as_ref() == Some(group));
+                if !exists {
+                    issues.push(LevelIssue::MissingGroup {
+                        wave: wave_number,
+                        group: group.clone(),
+                    });
+                }
+            }
+
+            let Some((from, _)) = spawners.iter().find(|(_, i)| *i == spawn.spawner) else {
+                issues.push(LevelIssue::MissingSpawner {
+                    wave: wave_number,
+                    spawner: spawn.spawner,
+                });
+                continue;
+            };
+
+            if !enemies.0.contains_key(&spawn.kind) && !missing_stats.contains(&spawn.kind) {
+                missing_stats.push(spawn.kind);
+                issues.push(LevelIssue::MissingEnemyStats(spawn.kind));
+            }
+
+            if checked.contains(&(spawn.spawner, spawn.kind)) {
+                continue;
+            }
+            checked.push((spawn.spawner, spawn.kind));
+
+            let terrain = enemies.stats(spawn.kind).terrain;
+
+            let reachable = bfs_reach(*from, |pos| {
+                NeighborCostIter::new(*pos, enemy_cost_fn(map, terrain)).map(|(pos, _)| pos)
+            })
+            .collect::<HashSet<_>>();
+
+            // Enemies head for the nearest home, and then the next one once it's
+            // destroyed, so every tile next to every home must be reachable.
+            for home in &homes {
+                let unreachable = NeighborCostIter::new(*home, enemy_cost_fn(map, terrain))
+                    .map(|(pos, _)| pos)
+                    .find(|pos| !reachable.contains(pos));
+
+                if let Some(target) = unreachable {
+                    issues.push(LevelIssue::UnreachableHome {
+                        wave: wave_number,
+                        spawner: spawn.spawner,
+                        kind: spawn.kind,
+                        from: *from,
+                        target,
+                        home: *home,
+                    });
+                }
+            }
+        }
+    }
+
+    for (pos, kind) in level.critters.iter().chain(&map.objects().critters) {
+        let Some(tile) = map.0.get(pos.y, pos.x) else {
+            issues.push(LevelIssue::CritterOutOfBounds {
+                kind: *kind,
+                pos: *pos,
+            });
+            continue;
+        };
+
+        if critter_cost_fn(map, *kind)((*pos).into()) == -1 {
+            issues.push(LevelIssue::CritterTile {
+                kind: *kind,
+                pos: *pos,
+                tile: *tile,
+            });
+        }
+    }
+
+    issues
+}
+
+/// Finds spawns that can never be triggered, which keep a wave without a timeout
+/// from ever ending.
+fn check_triggers(wave_number: usize, wave: &Wave, issues: &mut Vec<LevelIssue>) {
+    let spawns = &wave.spawns;
+
+    // Spawns that can be triggered once the ones before them have been.
+    let mut triggered = vec![false; spawns.len()];
+    let kills = |triggered: &[bool]| {
+        spawns
+            .iter()
+            .zip(triggered)
+            .filter(|(_, triggered)| **triggered)
+            .map(|(spawn, _)| spawn.num)
+            .sum::<usize>()
+    };
+    loop {
+        let mut changed = false;
+
+        for i in 0..spawns.len() {
+            if triggered[i] {
+                continue;
+            }
+
+            triggered[i] = match &spawns[i].trigger {
+                SpawnTrigger::WaveStart => true,
+                SpawnTrigger::GroupFinished(group) => spawns
+                    .iter()
+                    .zip(&triggered)
+                    .filter(|(spawn, _)| spawn.group.as_ref() == Some(group))
+                    .all(|(_, triggered)| *triggered),
+                SpawnTrigger::Kills(num) => kills(&triggered) >= *num,
+            };
+            changed |= triggered[i];
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut reported = vec![];
+    for (spawn, _) in spawns.iter().zip(&triggered).filter(|(_, t)| !**t) {
+        match &spawn.trigger {
+            SpawnTrigger::GroupFinished(group)
+                if spawn.group.as_ref() == Some(group) && !reported.contains(&group) =>
+            {
+                reported.push(group);
+                issues.push(LevelIssue::GroupWaitsForItself {
+                    wave: wave_number,
+                    group: group.clone(),
+                });
+            }
+            SpawnTrigger::Kills(num) => issues.push(LevelIssue::UnreachableKills {
+                wave: wave_number,
+                spawner: spawn.spawner,
+                kills: *num,
+                available: kills(&triggered),
+            }),
+            // Spawns that wait for blocked groups are fixed along with the groups.
+            _ => {}
+        }
+    }
+
+    let groups = spawns
+        .iter()
+        .filter_map(|spawn| spawn.group.as_ref())
+        .collect::<Vec<_>>();
+    let waits_for = |group: &&String| {
+        spawns
+            .iter()
+            .filter(|spawn| spawn.group.as_ref() == Some(*group))
+            .filter_map(|spawn| match &spawn.trigger {
+                SpawnTrigger::GroupFinished(other) if other != *group => Some(other),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for component in strongly_connected_components(&groups, waits_for) {
+        if component.len() < 2 {
+            continue;
+        }
+
+        let mut groups = component.into_iter().cloned().collect::<Vec<_>>();
+        groups.sort();
+        issues.push(LevelIssue::GroupCycle {
+            wave: wave_number,
+            groups,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        currency::Currency, level::Tuning, map_text::map_from_text, score::Scoring, spawner::Spawn,
+    };
+
+    fn spawn(num: usize, group: Option<&str>, trigger: SpawnTrigger) -> Spawn {
+        Spawn {
+            spawner: 0,
+            num,
+            delay: 0.,
+            interval: 1.,
+            hp: 1,
+            kind: EnemyKind::Skeleton,
+            speed: None,
+            damage: None,
+            reward: None,
+            group: group.map(String::from),
+            trigger,
+        }
+    }
+
+    fn level(waves: Vec<Wave>) -> LevelConfig {
+        LevelConfig {
+            map: String::new(),
+            strict_map: false,
+            palette: None,
+            workers: 1,
+            worker_spawn: None,
+            currency: Currency::ZERO,
+            waves,
+            critters: vec![],
+            tuning: Tuning::default(),
+            objectives: vec![],
+            bonus_objectives: vec![],
+            scoring: Scoring::default(),
+        }
+    }
+
+    fn wave(spawns: Vec<Spawn>) -> Wave {
+        Wave {
+            spawns,
+            timeout: None,
+        }
+    }
+
+    fn map(rows: &str) -> Map {
+        let legend = "S = Spawn\n: = Road\nH = Home\n# = Stone\nT = Forest\n---\n";
+        map_from_text(&format!("{legend}{rows}")).unwrap().0
+    }
+
+    fn issues(level: &LevelConfig, map: &Map) -> Vec<LevelIssue> {
+        let enemies = ron::from_str(include_str!("../assets/default.enemies.ron")).unwrap();
+        validate_level(level, map, &enemies)
+    }
+
+    fn trigger_issues(spawns: Vec<Spawn>) -> Vec<LevelIssue> {
+        let mut issues = vec![];
+        check_triggers(1, &wave(spawns), &mut issues);
+        issues
+    }
+
+    fn waits_for(group: &str) -> SpawnTrigger {
+        SpawnTrigger::GroupFinished(group.to_string())
+    }
+
+    #[test]
+    fn chained_triggers() {
+        let issues = trigger_issues(vec![
+            spawn(5, Some("a"), SpawnTrigger::WaveStart),
+            spawn(3, Some("b"), waits_for("a")),
+            spawn(1, None, waits_for("b")),
+            spawn(1, None, SpawnTrigger::Kills(8)),
+        ]);
+
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn group_waits_for_itself() {
+        let issues = trigger_issues(vec![
+            spawn(5, Some("a"), SpawnTrigger::WaveStart),
+            spawn(3, Some("a"), waits_for("a")),
+        ]);
+
+        assert!(matches!(
+            issues.as_slice(),
+            [LevelIssue::GroupWaitsForItself { group, .. }] if group == "a"
+        ));
+    }
+
+    #[test]
+    fn groups_wait_for_each_other() {
+        let issues = trigger_issues(vec![
+            spawn(5, Some("a"), waits_for("b")),
+            spawn(3, Some("b"), waits_for("a")),
+            spawn(1, None, SpawnTrigger::WaveStart),
+        ]);
+
+        assert!(matches!(
+            issues.as_slice(),
+            [LevelIssue::GroupCycle { groups, .. }] if groups == &["a", "b"]
+        ));
+    }
+
+    #[test]
+    fn too_many_kills() {
+        let issues = trigger_issues(vec![
+            spawn(5, None, SpawnTrigger::WaveStart),
+            spawn(3, None, SpawnTrigger::Kills(6)),
+        ]);
+
+        assert!(matches!(
+            issues.as_slice(),
+            [LevelIssue::UnreachableKills {
+                kills: 6,
+                available: 5,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn playable_level() {
+        let level = level(vec![wave(vec![spawn(5, None, SpawnTrigger::WaveStart)])]);
+        let issues = issues(&level, &map("####\nS::H\n####\n"));
+
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn empty_level() {
+        let issues = issues(&level(vec![]), &map("S::\n"));
+
+        assert!(matches!(
+            issues.as_slice(),
+            [
+                LevelIssue::NoHomes,
+                LevelIssue::NoWaves,
+                LevelIssue::UnusedSpawner { index: 0, .. },
+            ]
+        ));
+        assert!(!issues[2].is_error());
+    }
+
+    #[test]
+    fn missing_spawner() {
+        let spawn = Spawn {
+            spawner: 1,
+            ..spawn(5, None, SpawnTrigger::WaveStart)
+        };
+        let issues = issues(&level(vec![wave(vec![spawn])]), &map("S::H\n"));
+
+        assert!(issues
+            .iter()
+            .any(|issue| matches!(issue, LevelIssue::MissingSpawner { spawner: 1, .. })));
+    }
+
+    #[test]
+    fn only_forest_enemies_cross_forests() {
+        let map = map("#####\nS:T:H\n#####\n");
+
+        let skeletons = level(vec![wave(vec![spawn(5, None, SpawnTrigger::WaveStart)])]);
+        assert!(matches!(
+            issues(&skeletons, &map).as_slice(),
+            [LevelIssue::UnreachableHome {
+                kind: EnemyKind::Skeleton,
+                ..
+            }]
+        ));
+
+        let ents = level(vec![wave(vec![Spawn {
+            kind: EnemyKind::Ent,
+            ..spawn(5, None, SpawnTrigger::WaveStart)
+        }])]);
+        assert!(issues(&ents, &map).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use crate::{
+    enemy_table::Enemies,
+    hud::HudRoot,
+    level::LevelReloadedEvent,
+    settings::DifficultySetting,
+    spawner::{Spawner, SpawnerIndex},
+    tilemap::{AtlasHandle, SCALE, TILE_SIZE},
+    ui::{slice_image_mode, UiAssets, TITLE_TEXT},
+    waves::{self, Waves},
+    GameState,
+};
+
+/// The number of waves shown, including the current one.
+const PREVIEW_WAVES: usize = 3;
+const HIGHLIGHT: Color = Color::srgb(1.0, 0.8, 0.2);
+
+pub struct WavePreviewPlugin;
+impl Plugin for WavePreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), init)
+            .add_systems(
+                Update,
+                (update.after(waves::reload), highlight_spawner)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct WavePreviewContainer;
+
+/// A row of the preview describing one spawn.
+#[derive(Component)]
+pub struct WavePreviewSpawn {
+    pub spawner: usize,
+}
+
+fn init(mut commands: Commands, assets: Res<UiAssets>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            bottom: Val::Px(5.),
+            right: Val::Px(5.),
+            padding: UiRect::all(Val::Px(12.)),
+            row_gap: Val::Px(2.),
+            ..default()
+        },
+        ImageNode {
+            image: assets.nine_panel.clone(),
+            image_mode: slice_image_mode(),
+            ..default()
+        },
+        WavePreviewContainer,
+        HudRoot,
+        Name::new("WavePreview"),
+    ));
+}
+
+fn update(
+    mut commands: Commands,
+    waves: Option<Res<Waves>>,
+    mut reload_events: EventReader<LevelReloadedEvent>,
+    difficulty: Res<DifficultySetting>,
+    enemies: Enemies,
+    atlas_handle: Res<AtlasHandle>,
+    container_query: Query<Entity, With<WavePreviewContainer>>,
+) {
+    let Some(waves) = waves else {
+        return;
+    };
+
+    // Waves may be replaced without changing `Waves` when a level is reloaded.
+    let reloaded = !reload_events.is_empty();
+    reload_events.clear();
+    if !waves.is_changed() && !reloaded {
+        return;
+    }
+
+    let Ok(container) = container_query.single() else {
+        return;
+    };
+
+    commands.entity(container).despawn_related::<Children>();
+
+    let font = TextFont {
+        font_size: 12.0,
+        ..default()
+    };
+
+    commands.entity(container).with_children(|parent| {
+        parent.spawn((
+            Text::new("Upcoming waves"),
+            TextFont {
+                font_size: 15.0,
+                ..default()
+            },
+            TextColor(TITLE_TEXT),
+        ));
+
+        for (i, wave) in waves.upcoming(PREVIEW_WAVES) {
+            let title = if i == waves.current {
+                format!("Wave {} (current)", i + 1)
+            } else {
+                format!("Wave {}", i + 1)
+            };
+
+            parent.spawn((
+                Node {
+                    margin: UiRect::top(Val::Px(6.)),
+                    ..default()
+                },
+                Text::new(title),
+                font.clone(),
+                TextColor(TITLE_TEXT),
+            ));
+
+            for spawn in &wave.spawns {
+                let stats = enemies.stats(spawn.kind);
+
+                parent.spawn((
+                    Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(5.),
+                        ..default()
+                    },
+                    Interaction::default(),
+                    FocusPolicy::Block,
+                    WavePreviewSpawn {
+                        spawner: spawn.spawner,
+                    },
+                    children![
+                        (
+                            Node {
+                                width: Val::Px(TILE_SIZE.x * SCALE.x / 2.),
+                                height: Val::Px(TILE_SIZE.y * SCALE.y / 2.),
+                                ..default()
+                            },
+                            ImageNode {
+                                image: atlas_handle.image.clone(),
+                                texture_atlas: Some(TextureAtlas {
+                                    layout: atlas_handle.layout.clone(),
+                                    index: stats.sprite,
+                                }),
+                                ..default()
+                            },
+                        ),
+                        (
+                            Text::new(format!(
+                                "{}x {:?}, {} hp, spawner {}",
+                                spawn.num,
+                                spawn.kind,
+                                difficulty.scale_hp(stats.hp(spawn.hp)),
+                                spawn.spawner
+                            )),
+                            font.clone(),
+                            TextColor(TITLE_TEXT),
+                        ),
+                    ],
+                ));
+            }
+        }
+
+        if waves.current().is_none() {
+            parent.spawn((Text::new("None"), font.clone(), TextColor(TITLE_TEXT)));
+        }
+    });
+}
+
+/// Tints the spawner of the spawn being hovered in the preview.
+fn highlight_spawner(
+    preview_query: Query<(&Interaction, &WavePreviewSpawn)>,
+    changed_query: Query<(), (Changed<Interaction>, With<WavePreviewSpawn>)>,
+    mut removed: RemovedComponents<WavePreviewSpawn>,
+    mut spawner_query: Query<(&mut Sprite, &SpawnerIndex), With<Spawner>>,
+) {
+    // Rows are removed whenever the preview is rebuilt, possibly while hovered.
+    let rebuilt = !removed.is_empty();
+    removed.clear();
+    if changed_query.is_empty() && !rebuilt {
+        return;
+    }
+
+    let hovered = preview_query
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, spawn)| spawn.spawner);
+
+    for (mut sprite, index) in &mut spawner_query {
+        let color = if Some(index.0) == hovered {
+            HIGHLIGHT
+        } else {
+            Color::WHITE
+        };
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
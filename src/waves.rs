@@ -1,16 +1,19 @@
 use bevy::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    level::{LevelConfig, LevelHandle},
+    endless::{EndlessWaves, GameMode},
+    level::{LevelConfig, LevelHandle, LevelReloadedEvent},
     spawner::Spawn,
+    tilemap::{Map, TilemapHandle},
     GameState,
 };
 
 pub struct WavesPlugin;
 impl Plugin for WavesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), init);
+        app.add_systems(OnEnter(GameState::Playing), init)
+            .add_systems(Update, reload.run_if(in_state(GameState::Playing)));
     }
 }
 
@@ -18,28 +21,77 @@ impl Plugin for WavesPlugin {
 pub struct Waves {
     pub current: usize,
     pub waves: Vec<Wave>,
+    /// Generates more waves once `waves` runs out, in endless mode.
+    pub endless: Option<EndlessWaves>,
 }
 impl Waves {
+    pub fn endless(waves: Vec<Wave>, spawners: Vec<usize>) -> Self {
+        let mut endless = EndlessWaves::new(&waves, spawners);
+        if waves.is_empty() {
+            endless.generate(1);
+        }
+
+        Self {
+            current: 0,
+            waves,
+            endless: Some(endless),
+        }
+    }
     pub fn current(&self) -> Option<&Wave> {
-        self.waves.get(self.current)
+        self.waves
+            .get(self.current)
+            .or_else(|| self.endless.as_ref()?.wave.as_ref())
+    }
+    /// The current wave and the ones after it, with their indices.
+    ///
+    /// In endless mode, generated waves are only known once they start.
+    pub fn upcoming(&self, num: usize) -> impl Iterator<Item = (usize, &Wave)> {
+        let current = self.current().map(|wave| (self.current, wave));
+        let later = (self.current + 1..)
+            .map_while(|i| self.waves.get(i).map(|wave| (i, wave)))
+            .take(num.saturating_sub(1));
+
+        current.into_iter().chain(later)
     }
     pub fn advance(&mut self) -> Option<&Wave> {
         self.current += 1;
+
+        if let Some(endless) = &mut self.endless {
+            if self.current >= self.waves.len() {
+                endless.generate(self.current + 1 - self.waves.len());
+            }
+        }
+
         self.current()
     }
     pub fn reset(&mut self) {
         self.current = 0;
+
+        if let Some(endless) = &mut self.endless {
+            endless.wave = None;
+            if self.waves.is_empty() {
+                endless.generate(1);
+            }
+        }
     }
 }
 impl From<Vec<Wave>> for Waves {
     fn from(waves: Vec<Wave>) -> Self {
-        Self { current: 0, waves }
+        Self {
+            current: 0,
+            waves,
+            endless: None,
+        }
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Wave {
     pub spawns: Vec<Spawn>,
+    /// Start the next wave after this many seconds, even if some spawns haven't
+    /// finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f32>,
 }
 
 #[derive(Event)]
@@ -49,8 +101,70 @@ pub fn init(
     mut commands: Commands,
     level_handle: Res<LevelHandle>,
     levels: Res<Assets<LevelConfig>>,
+    mode: Res<GameMode>,
+    tilemap_handle: Res<TilemapHandle>,
+    maps: Res<Assets<Map>>,
 ) {
-    if let Some(level) = levels.get(&level_handle.0) {
-        commands.insert_resource::<Waves>(level.waves.clone().into());
+    let Some(level) = levels.get(&level_handle.0) else {
+        return;
+    };
+
+    let waves = match *mode {
+        GameMode::Campaign => level.waves.clone().into(),
+        GameMode::Endless => {
+            let spawners = maps
+                .get(&tilemap_handle.0)
+                .map(|map| map.spawners().into_iter().map(|(_, i)| i).collect())
+                .unwrap_or_default();
+
+            Waves::endless(level.waves.clone(), spawners)
+        }
+    };
+
+    commands.insert_resource(waves);
+}
+
+/// Replaces the waves with the reloaded level's. The wave in progress is only
+/// restarted if it was changed, so that tweaking later waves doesn't interrupt it.
+pub fn reload(
+    mut events: EventReader<LevelReloadedEvent>,
+    levels: Res<Assets<LevelConfig>>,
+    level_handle: Res<LevelHandle>,
+    mut waves: ResMut<Waves>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    let Some(level) = levels.get(&level_handle.0) else {
+        return;
+    };
+
+    // Once endless mode has moved on to generated waves, the level's own waves
+    // have all been played, and waves added by the reload are skipped so that the
+    // generated wave isn't interrupted.
+    if waves.endless.is_some() && waves.current >= waves.waves.len() {
+        return;
+    }
+
+    // Changing `Waves` restarts the spawners.
+    if waves.waves.get(waves.current) != level.waves.get(waves.current) {
+        info!("Wave {}: Restarting changed wave.", waves.current);
+        waves.waves.clone_from(&level.waves);
+
+        // The reload removed the wave in progress, so carry on with generated ones.
+        let current = waves.current;
+        let authored = waves.waves.len();
+        if let Some(endless) = &mut waves.endless {
+            if current >= authored {
+                endless.generate(current + 1 - authored);
+            }
+        }
+    } else {
+        waves
+            .bypass_change_detection()
+            .waves
+            .clone_from(&level.waves);
     }
 }
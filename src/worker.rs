@@ -1,16 +1,18 @@
 use crate::{
+    damage::{apply_damage, DamageType, DamagedEvent},
     designate_tool::{DesignationKind, Designations},
     hit_points::HitPoints,
     layer,
-    level::{LevelConfig, LevelHandle},
+    level::{LevelConfig, LevelHandle, DEFAULT_TOWER_HIT_POINTS},
     movement::{MovingProgress, Speed},
-    pathfinding::{heuristic, worker_cost_fn, NeighborCostIter, PathState},
-    settings::SfxSetting,
+    particle::ParticleKind,
+    pathfinding::{heuristic, worker_cost_fn, NeighborCostIter, PathState, SquareAreaCostIter},
+    settings::{ParticlesSetting, SfxSetting},
     sound::SoundAssets,
     stats::Stats,
     stone::HitStoneEvent,
     tilemap::{AtlasHandle, Map, TileEntities, TileKind, TilePos},
-    tower::BuildTowerEvent,
+    tower::{BuildTowerEvent, Tower},
     GameState,
 };
 use bevy::{audio::Volume, prelude::*};
@@ -21,20 +23,32 @@ pub struct WorkerPlugin;
 impl Plugin for WorkerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnWorkerEvent>()
+            .add_event::<WorkerDiedEvent>()
             .init_resource::<WorkerSortTimer>()
             .init_resource::<WorkerRng>()
+            .init_resource::<Reinforcement>()
             .add_systems(Update, spawn.run_if(in_state(GameState::Playing)))
             .add_systems(OnEnter(GameState::Playing), init)
             .add_systems(
                 Update,
-                (find_job, do_job, tick_cooldown, sort_workers)
+                (
+                    find_job,
+                    do_job,
+                    tick_cooldown,
+                    sort_workers,
+                    bleed.after(apply_damage),
+                    die.after(apply_damage),
+                    reinforce,
+                )
                     .run_if(in_state(GameState::Playing)),
             )
             .add_systems(OnExit(GameState::GameOver), cleanup);
     }
 }
 
-const WORKER_SPRITES: [usize; 2] = [103 * 14, 103 * 15];
+pub const WORKER_SPRITES: [usize; 2] = [103 * 14, 103 * 15];
+/// How long it takes for a new worker to arrive after every worker has been lost.
+pub const REINFORCEMENT_SECS: f32 = 30.;
 
 #[derive(Component, Default)]
 #[require(Sprite, HitPoints, TilePos, MovingProgress, Speed, WorkCooldown)]
@@ -46,7 +60,12 @@ pub struct Idle;
 #[derive(Component)]
 pub enum Job {
     Dig(TilePos),
-    Build { hit_points: HitPoints, pos: TilePos },
+    Build {
+        hit_points: HitPoints,
+        pos: TilePos,
+        damage_type: DamageType,
+    },
+    Repair(TilePos),
 }
 
 #[derive(Component)]
@@ -59,6 +78,16 @@ impl Default for WorkCooldown {
 #[derive(Event)]
 pub struct SpawnWorkerEvent;
 
+#[derive(Event)]
+pub struct WorkerDiedEvent {
+    /// The number of workers that are still alive.
+    pub remaining: usize,
+}
+
+/// Counts down to a new worker arriving after every worker has been lost.
+#[derive(Resource, Default)]
+pub struct Reinforcement(pub Option<Timer>);
+
 #[derive(Resource)]
 pub struct WorkerSortTimer(Timer);
 impl Default for WorkerSortTimer {
@@ -80,12 +109,19 @@ fn spawn(
     mut events: EventReader<SpawnWorkerEvent>,
     atlas_handle: Res<AtlasHandle>,
     tilemap_query: Query<&Map>,
+    levels: Res<Assets<LevelConfig>>,
+    level_handle: Res<LevelHandle>,
     mut rng: ResMut<WorkerRng>,
+    mut next_home: Local<usize>,
 ) {
     if events.is_empty() {
         return;
     }
 
+    let worker_spawn = levels
+        .get(&level_handle.0)
+        .and_then(|level| level.worker_spawn);
+
     for _ in events.read() {
         let Ok(tilemap) = tilemap_query.single() else {
             continue;
@@ -94,10 +130,24 @@ fn spawn(
         let index = *WORKER_SPRITES.choose(&mut rng.0).unwrap();
         let color = Color::hsl(rng.0.gen_range(0.0..=360.0), 0.9, 0.5);
 
-        let home = (60, 30);
-        let pos = TilePos {
-            x: rng.0.gen_range((home.0 - 2)..(home.0 + 2)),
-            y: rng.0.gen_range((home.1 - 2)..(home.1 + 2)),
+        let anchors = match worker_spawn {
+            Some(pos) => vec![pos],
+            None => tilemap.homes(),
+        };
+        let anchor = if anchors.is_empty() {
+            TilePos {
+                x: tilemap.0.cols() / 2,
+                y: tilemap.0.rows() / 2,
+            }
+        } else {
+            let anchor = anchors[*next_home % anchors.len()];
+            *next_home += 1;
+            anchor
+        };
+
+        let Some(pos) = spawn_pos(tilemap, anchor, &mut rng.0) else {
+            warn!("Couldn't find anywhere to spawn a worker.");
+            continue;
         };
         let world = tilemap.pos_to_world(pos);
 
@@ -127,6 +177,23 @@ fn spawn(
     }
 }
 
+/// Chooses a random walkable tile near `anchor`, or the closest walkable tile to
+/// it if there are none nearby.
+fn spawn_pos(map: &Map, anchor: TilePos, rng: &mut SmallRng) -> Option<TilePos> {
+    let nearby = SquareAreaCostIter::new(anchor, 2, worker_cost_fn(map))
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+    if let Some(pos) = nearby.choose(rng) {
+        return Some(*pos);
+    }
+
+    let cost_fn = worker_cost_fn(map);
+    (0..map.0.cols())
+        .flat_map(|x| (0..map.0.rows()).map(move |y| TilePos { x, y }))
+        .filter(|pos| cost_fn((*pos).into()) != -1)
+        .min_by_key(|pos| heuristic(*pos, anchor))
+}
+
 fn init(
     mut events: EventWriter<SpawnWorkerEvent>,
     levels: Res<Assets<LevelConfig>>,
@@ -147,6 +214,8 @@ fn find_job(
     query: Query<(Entity, &TilePos), (With<Worker>, With<Idle>, Without<PathState>)>,
     mut designations: ResMut<Designations>,
     tilemap_query: Query<&Map>,
+    levels: Res<Assets<LevelConfig>>,
+    level_handle: Res<LevelHandle>,
 ) {
     let Ok(map) = tilemap_query.single() else {
         return;
@@ -156,6 +225,11 @@ fn find_job(
         return;
     }
 
+    let tower_hit_points = levels
+        .get(&level_handle.0)
+        .map(|level| level.tuning.tower_hit_points())
+        .unwrap_or(DEFAULT_TOWER_HIT_POINTS);
+
     let mut potential_jobs = designations
         .0
         .iter()
@@ -186,7 +260,7 @@ fn find_job(
         potential_jobs.sort_by_key(|a| {
             let dist = u32::MAX - heuristic(a.0, *pos);
             let tower_with_no_workers =
-                matches!(a.1.kind, DesignationKind::BuildTower) && a.1.workers < 1;
+                matches!(a.1.kind, DesignationKind::BuildTower(_)) && a.1.workers < 1;
 
             (tower_with_no_workers, dist)
         });
@@ -213,12 +287,16 @@ fn find_job(
             DesignationKind::Dig => {
                 command.insert(Job::Dig(goal));
             }
-            DesignationKind::BuildTower => {
+            DesignationKind::BuildTower(damage_type) => {
                 command.insert(Job::Build {
-                    hit_points: HitPoints::full(10),
+                    hit_points: HitPoints::full(tower_hit_points),
                     pos: goal,
+                    damage_type,
                 });
             }
+            DesignationKind::Repair => {
+                command.insert(Job::Repair(goal));
+            }
             _ => {}
         }
 
@@ -239,9 +317,10 @@ fn do_job(
         (Entity, &TilePos, &mut Job, &mut WorkCooldown),
         (With<Worker>, Without<Idle>, Without<PathState>),
     >,
-    dig_query: Query<&HitPoints>,
+    dig_query: Query<&HitPoints, Without<Tower>>,
+    mut tower_query: Query<&mut HitPoints, With<Tower>>,
     tile_kind_query: Query<&TileKind>,
-    mut tilemap_query: Query<&TileEntities>,
+    tilemap_query: Query<(&Map, &TileEntities)>,
     mut events: EventWriter<HitStoneEvent>,
     mut tower_events: EventWriter<BuildTowerEvent>,
     sound_assets: Res<SoundAssets>,
@@ -252,7 +331,7 @@ fn do_job(
         return;
     }
 
-    let Ok(map_entities) = tilemap_query.single_mut() else {
+    let Ok((map, map_entities)) = tilemap_query.single() else {
         return;
     };
 
@@ -298,7 +377,11 @@ fn do_job(
 
                 cooldown.0.reset();
             }
-            Job::Build { hit_points, pos } => {
+            Job::Build {
+                hit_points,
+                pos,
+                damage_type,
+            } => {
                 let Some(tile_entity) = map_entities.0[(pos.y, pos.x)] else {
                     warn!("Working trying to build at position without entity.");
                     commands.entity(entity).insert(Idle).remove::<Job>();
@@ -314,7 +397,7 @@ fn do_job(
                     continue;
                 };
 
-                if !kind.buildable() {
+                if !map.properties().buildable(*kind) {
                     warn!("Working trying to build at position without tile kind.");
                     commands.entity(entity).insert(Idle).remove::<Job>();
                     continue;
@@ -333,9 +416,37 @@ fn do_job(
 
                 if hit_points.is_zero() {
                     stats.towers += 1;
-                    tower_events.write(BuildTowerEvent(*pos));
+                    tower_events.write(BuildTowerEvent(*pos, *damage_type));
+                }
+
+                cooldown.0.reset();
+            }
+            Job::Repair(pos) => {
+                let Some(mut hp) = map_entities.0[(pos.y, pos.x)]
+                    .and_then(|tile_entity| tower_query.get_mut(tile_entity).ok())
+                else {
+                    // The tower was destroyed.
+                    commands.entity(entity).insert(Idle).remove::<Job>();
+                    continue;
+                };
+
+                if hp.is_full() {
+                    commands.entity(entity).insert(Idle).remove::<Job>();
+                    continue;
+                }
+
+                if !cooldown.0.finished() {
+                    continue;
                 }
 
+                hp.add(1);
+
+                commands.spawn((
+                    AudioPlayer(sound_assets.pickaxe.clone()),
+                    PlaybackSettings::DESPAWN
+                        .with_volume(Volume::Linear(**sfx_setting as f32 / 100.)),
+                ));
+
                 cooldown.0.reset();
             }
         }
@@ -366,8 +477,102 @@ fn sort_workers(
     }
 }
 
+fn bleed(
+    mut commands: Commands,
+    mut events: EventReader<DamagedEvent>,
+    query: Query<&Transform, With<Worker>>,
+    particle_settings: Res<ParticlesSetting>,
+) {
+    for event in events.read() {
+        // Workers that are killed burst into particles when they die.
+        if event.killed {
+            continue;
+        }
+
+        let Ok(transform) = query.get(event.target) else {
+            continue;
+        };
+
+        for _ in 0..particle_settings.hit_amt() {
+            commands.spawn((
+                ParticleKind::Blood,
+                Transform::from_translation(transform.translation),
+            ));
+        }
+    }
+}
+
+fn die(
+    mut commands: Commands,
+    query: Query<(Entity, &HitPoints, &Transform, Option<&Job>), With<Worker>>,
+    mut designations: ResMut<Designations>,
+    mut stats: ResMut<Stats>,
+    mut events: EventWriter<WorkerDiedEvent>,
+    mut reinforcement: ResMut<Reinforcement>,
+    sound_assets: Res<SoundAssets>,
+    sfx_setting: Res<SfxSetting>,
+    particle_settings: Res<ParticlesSetting>,
+) {
+    let remaining = query.iter().filter(|(_, hp, ..)| !hp.is_zero()).count();
+
+    for (entity, hp, transform, job) in &query {
+        if !hp.is_zero() {
+            continue;
+        }
+
+        stats.workers_lost += 1;
+
+        // Let another worker take the job.
+        if let Some(Job::Dig(pos) | Job::Build { pos, .. } | Job::Repair(pos)) = job {
+            if let Some(designation) = designations.0.get_mut(pos) {
+                designation.workers = designation.workers.saturating_sub(1);
+            }
+        }
+
+        for _ in 0..particle_settings.kill_amt() {
+            commands.spawn((
+                ParticleKind::Blood,
+                Transform::from_translation(transform.translation),
+            ));
+        }
+
+        commands.spawn((
+            AudioPlayer(sound_assets.bad.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(**sfx_setting as f32 / 100.)),
+        ));
+
+        commands.entity(entity).despawn();
+
+        events.write(WorkerDiedEvent { remaining });
+
+        if remaining == 0 && reinforcement.0.is_none() {
+            reinforcement.0 = Some(Timer::from_seconds(REINFORCEMENT_SECS, TimerMode::Once));
+        }
+    }
+}
+
+/// Sends a single new worker once every worker has been lost, so that the level
+/// can still be finished.
+fn reinforce(
+    mut reinforcement: ResMut<Reinforcement>,
+    mut events: EventWriter<SpawnWorkerEvent>,
+    time: Res<Time>,
+) {
+    let Some(timer) = &mut reinforcement.0 else {
+        return;
+    };
+
+    timer.tick(time.delta());
+    if timer.finished() {
+        events.write(SpawnWorkerEvent);
+        reinforcement.0 = None;
+    }
+}
+
 fn cleanup(mut commands: Commands, query: Query<Entity, With<Worker>>) {
     for entity in &query {
         commands.entity(entity).despawn();
     }
+
+    commands.insert_resource(Reinforcement::default());
 }